@@ -1,23 +1,69 @@
 use std::{
     env,
     fs::{self},
+    io,
+    process::ExitCode,
 };
 
 use bumpalo::Bump;
-use parser::parser::Parser;
+use parser::{batch::validate_paths, parser::Parser, validate::validate_reader};
 
-fn main() {
+fn main() -> ExitCode {
     let Some(path) = env::args().nth(1) else {
         panic!("missing path")
     };
 
+    if path == "--check" && env::args().len() > 2 {
+        let report = validate_paths(env::args().skip(2));
+
+        println!("{report}");
+
+        return match report.failed {
+            0 => ExitCode::SUCCESS,
+            _ => ExitCode::FAILURE,
+        };
+    }
+
+    // Streams stdin through the validator without buffering it
+    if path == "--check" {
+        return match validate_reader(io::stdin().lock()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Reformats the document instead of flattening it
+    let pretty = path == "--pretty";
+    let path = if pretty {
+        env::args().nth(2).expect("missing path")
+    } else {
+        path
+    };
+
     let input = fs::read_to_string(path).unwrap();
 
     let bump = Bump::new();
     let parser = Parser::new(&input);
 
     match parser.parse(&bump) {
-        Ok(res) => println!("{:#?}", res.flattened()),
+        // A number too large for an `f64` parses as infinite, which JSON can't represent
+        Ok(res) if pretty => match res.to_json_pretty(2) {
+            Ok(json) => println!("{json}"),
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        },
+        Ok(res) => println!("{}", res.flattened_json()),
+        Err(e) if pretty => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
         Err(e) => eprintln!("{e}"),
     }
+
+    ExitCode::SUCCESS
 }
@@ -1,17 +1,82 @@
-use bumpalo::collections::Vec;
-use std::{borrow::Cow, collections::BTreeMap};
+use bumpalo::{Bump, collections::Vec};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io::{self, Write},
+    ops::{ControlFlow, Index, IndexMut},
+};
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::options::{Indent, NonFinite, SerializeOptions};
+
+/// Type of [`JsonProperty::key`], a plain borrowed `&str` with the `borrowed-keys` feature to
+/// save the `Cow` discriminant and the branch on every access.
+#[cfg(not(feature = "borrowed-keys"))]
+pub type JsonKey<'a> = std::borrow::Cow<'a, str>;
+#[cfg(feature = "borrowed-keys")]
+pub type JsonKey<'a> = &'a str;
+
+/// Wraps a key borrowed from the input as a [`JsonKey`], whichever type that is.
+#[cfg(not(feature = "borrowed-keys"))]
+pub(crate) fn borrowed_key(key: &str) -> JsonKey<'_> {
+    std::borrow::Cow::Borrowed(key)
+}
+
+#[cfg(feature = "borrowed-keys")]
+pub(crate) fn borrowed_key(key: &str) -> JsonKey<'_> {
+    key
+}
+
+#[derive(Clone, PartialEq)]
 pub struct JsonProperty<'a> {
-    pub key: Cow<'a, str>,
+    pub key: JsonKey<'a>,
     pub value: JsonValue<'a>,
+    /// Comments directly preceding the key when parsed with
+    /// [`ParserOptions::allow_comments`](crate::options::ParserOptions::allow_comments).
+    pub leading_comment: Option<&'a str>,
+}
+
+impl std::fmt::Debug for JsonProperty<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("JsonProperty");
+
+        debug.field("key", &self.key).field("value", &self.value);
+
+        // Only shown when present so that comment-free documents print as before
+        if let Some(comment) = self.leading_comment {
+            debug.field("leading_comment", &comment);
+        }
+
+        debug.finish()
+    }
+}
+
+impl<'a> JsonProperty<'a> {
+    /// Returns the key as a `&str`, whichever type [`JsonKey`] is.
+    #[cfg(not(feature = "borrowed-keys"))]
+    pub fn key_str(&self) -> &str {
+        &self.key
+    }
+
+    #[cfg(feature = "borrowed-keys")]
+    pub fn key_str(&self) -> &str {
+        self.key
+    }
+
+    /// Returns a property with the same key and comment holding `value` instead.
+    pub fn with_value(&self, value: JsonValue<'a>) -> Self {
+        Self {
+            key: JsonKey::clone(&self.key),
+            value,
+            leading_comment: self.leading_comment,
+        }
+    }
 }
 
 impl<'a> From<(&'a str, JsonValue<'a>)> for JsonProperty<'a> {
     fn from(item: (&'a str, JsonValue<'a>)) -> Self {
         Self {
-            key: item.0.into(),
+            key: borrowed_key(item.0),
             value: item.1,
+            leading_comment: None,
         }
     }
 }
@@ -21,234 +86,1584 @@ pub enum JsonValue<'a> {
     Null,
     Boolean(bool),
     Number(f64),
+    /// Decoded text, borrowed from the input when it has no escapes and otherwise allocated
+    /// in the arena.
+    ///
+    /// The one exception is a number kept as text by [`Parser::parse_with_raw_numbers`],
+    /// which holds the number's source text as it appears in the input.
+    ///
+    /// [`Parser::parse_with_raw_numbers`]: crate::parser::Parser::parse_with_raw_numbers
     String(&'a str),
     Object(Vec<'a, JsonProperty<'a>>),
     Array(Vec<'a, JsonValue<'a>>),
 }
 
-impl JsonValue<'_> {
-    pub fn flattened(&self) -> BTreeMap<String, String> {
-        let mut res = BTreeMap::new();
+impl<'a> JsonValue<'a> {
+    pub fn as_object(&self) -> Option<&Vec<'a, JsonProperty<'a>>> {
+        match self {
+            JsonValue::Object(properties) => Some(properties),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Vec<'a, JsonProperty<'a>>> {
+        match self {
+            JsonValue::Object(properties) => Some(properties),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the property `key` if this is an object containing it, the
+    /// non-panicking form of `value["key"]`. When an object repeats a key the last occurrence
+    /// wins, matching [`JsonValue::pointer`].
+    ///
+    /// A key that is present with a `null` value gives `Some(&JsonValue::Null)`, while a
+    /// missing key gives `None`, so the two can be told apart.
+    pub fn get(&self, key: &str) -> Option<&JsonValue<'a>> {
+        self.as_object()?
+            .iter()
+            .rev()
+            .find(|property| property.key == key)
+            .map(|property| &property.value)
+    }
+
+    /// Returns a mutable reference to the value of the property `key`, picking the same
+    /// property as [`JsonValue::get`].
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue<'a>> {
+        self.as_object_mut()?
+            .iter_mut()
+            .rev()
+            .find(|property| property.key == key)
+            .map(|property| &mut property.value)
+    }
+
+    /// Returns the value of the property `key` if this is an object containing it, the same as
+    /// [`JsonValue::get`], for call sites that want to spell out that a key present with a
+    /// `null` value gives `Some(&JsonValue::Null)` while a missing key gives `None`.
+    pub fn get_present(&self, key: &str) -> Option<&JsonValue<'a>> {
+        self.get(key)
+    }
+
+    /// Returns the members of an object sorted by key, leaving the object itself in document
+    /// order. Repeated keys keep their relative order, and anything other than an object
+    /// gives an empty `Vec`.
+    pub fn entries_sorted(&self) -> std::vec::Vec<(&str, &JsonValue<'a>)> {
+        let mut entries: std::vec::Vec<_> = self
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|property| (property.key_str(), &property.value))
+            .collect();
+
+        entries.sort_by_key(|&(key, _)| key);
+        entries
+    }
 
-        self.flatten("", &mut res);
+    /// Follows a chain of objects with exactly one property, such as
+    /// `{"data": {"result": ...}}`, down to the first value that isn't one.
+    pub fn unwrap_single(&self) -> &JsonValue<'a> {
+        let mut value = self;
 
-        res
+        while let JsonValue::Object(properties) = value
+            && let [property] = properties.as_slice()
+        {
+            value = &property.value;
+        }
+
+        value
     }
 
-    fn flatten(&self, prefix: &str, res: &mut BTreeMap<String, String>) {
+    /// Returns the name of the value's JSON type: `"null"`, `"boolean"`, `"number"`,
+    /// `"string"`, `"object"` or `"array"`.
+    pub fn type_name(&self) -> &'static str {
         match self {
-            JsonValue::Null => {
-                res.insert(prefix.to_owned(), "null".to_string());
-            }
-            JsonValue::Boolean(val) => {
-                res.insert(prefix.to_owned(), val.to_string());
+            JsonValue::Null => "null",
+            JsonValue::Boolean(_) => "boolean",
+            JsonValue::Number(_) => "number",
+            JsonValue::String(_) => "string",
+            JsonValue::Object(_) => "object",
+            JsonValue::Array(_) => "array",
+        }
+    }
+
+    /// Returns `true` for a scalar, or an object or array containing only scalars, such as
+    /// something that can be shown as a single table row rather than a tree.
+    pub fn is_flat(&self) -> bool {
+        let is_scalar =
+            |value: &JsonValue| !matches!(value, JsonValue::Object(_) | JsonValue::Array(_));
+
+        match self {
+            JsonValue::Object(properties) => {
+                properties.iter().all(|property| is_scalar(&property.value))
             }
-            JsonValue::Number(val) => {
-                res.insert(prefix.to_owned(), val.to_string());
+            JsonValue::Array(json_values) => json_values.iter().all(is_scalar),
+            _ => true,
+        }
+    }
+
+    /// Counts the scalars (nulls, booleans, numbers and strings) in the value, which is one
+    /// per entry of [`JsonValue::flattened_ordered`].
+    pub fn leaf_count(&self) -> usize {
+        match self {
+            JsonValue::Object(properties) => properties
+                .iter()
+                .map(|property| property.value.leaf_count())
+                .sum(),
+            JsonValue::Array(json_values) => json_values.iter().map(JsonValue::leaf_count).sum(),
+            _ => 1,
+        }
+    }
+
+    /// Collects every object key found anywhere in the value, including inside arrays.
+    pub fn all_keys(&self) -> BTreeSet<String> {
+        let mut keys = BTreeSet::new();
+
+        self.collect_keys(&mut keys);
+
+        keys
+    }
+
+    /// For an array of objects, maps each key found in the elements to the set of
+    /// [`JsonValue::type_name`]s its values have, so keys with inconsistent types stand out.
+    /// Elements that aren't objects are skipped, and any value other than an array gives an
+    /// empty map.
+    pub fn key_type_profile(&self) -> BTreeMap<String, BTreeSet<&'static str>> {
+        let mut profile: BTreeMap<String, BTreeSet<&'static str>> = BTreeMap::new();
+
+        let objects = self
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(JsonValue::as_object);
+
+        for property in objects.flatten() {
+            let type_name = property.value.type_name();
+
+            match profile.get_mut(property.key_str()) {
+                Some(types) => {
+                    types.insert(type_name);
+                }
+                None => {
+                    profile.insert(property.key.to_string(), BTreeSet::from([type_name]));
+                }
             }
-            JsonValue::String(val) => {
-                res.insert(prefix.to_owned(), val.to_string());
+        }
+
+        profile
+    }
+
+    /// For an array of objects, returns the keys found in every element, in the order they
+    /// first appear. Returns `None` for anything else, including an array with an element
+    /// that isn't an object.
+    pub fn array_common_keys(&self) -> Option<std::vec::Vec<&str>> {
+        let mut objects = self
+            .as_array()?
+            .iter()
+            .map(JsonValue::as_object)
+            .collect::<Option<std::vec::Vec<_>>>()?
+            .into_iter();
+
+        let mut common = std::vec::Vec::new();
+
+        // Any key missing from the first object is missing from the intersection too
+        for property in objects.next().into_iter().flatten() {
+            if !common.contains(&property.key_str()) {
+                common.push(property.key_str());
             }
+        }
+
+        for properties in objects {
+            let keys: HashSet<&str> = properties
+                .iter()
+                .map(|property| property.key_str())
+                .collect();
+
+            common.retain(|key| keys.contains(key));
+        }
+
+        Some(common)
+    }
+
+    fn collect_keys(&self, keys: &mut BTreeSet<String>) {
+        match self {
             JsonValue::Object(properties) => {
                 for property in properties {
-                    let new_prefix = if prefix.is_empty() {
-                        property.key.to_string()
+                    if !keys.contains(property.key_str()) {
+                        keys.insert(property.key.to_string());
+                    }
+
+                    property.value.collect_keys(keys);
+                }
+            }
+            JsonValue::Array(json_values) => {
+                for value in json_values {
+                    value.collect_keys(keys);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<'a, JsonValue<'a>>> {
+        match self {
+            JsonValue::Array(json_values) => Some(json_values),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<'a, JsonValue<'a>>> {
+        match self {
+            JsonValue::Array(json_values) => Some(json_values),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of an array made up only of numbers, or `None` for anything else,
+    /// including an array with any element of another type.
+    pub fn as_f64_vec(&self) -> Option<std::vec::Vec<f64>> {
+        self.as_array()?
+            .iter()
+            .map(|value| match value {
+                JsonValue::Number(n) => Some(*n),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the elements of an array made up only of strings, like
+    /// [`JsonValue::as_f64_vec`].
+    pub fn as_str_vec(&self) -> Option<std::vec::Vec<&'a str>> {
+        self.as_array()?
+            .iter()
+            .map(|value| match value {
+                JsonValue::String(s) => Some(*s),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the elements of an array made up only of booleans, like
+    /// [`JsonValue::as_f64_vec`].
+    pub fn as_bool_vec(&self) -> Option<std::vec::Vec<bool>> {
+        self.as_array()?
+            .iter()
+            .map(|value| match value {
+                JsonValue::Boolean(b) => Some(*b),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Calls `f` on this value and then on every nested value in document order, stopping as
+    /// soon as `f` returns [`ControlFlow::Break`].
+    ///
+    /// Children are visited after `f` has run on their parent, so a node that `f` replaces is
+    /// traversed in its new form.
+    pub fn visit_mut<F>(&mut self, mut f: F) -> ControlFlow<()>
+    where
+        F: FnMut(&mut JsonValue<'a>) -> ControlFlow<()>,
+    {
+        self.visit_mut_with(&mut f)
+    }
+
+    fn visit_mut_with<F>(&mut self, f: &mut F) -> ControlFlow<()>
+    where
+        F: FnMut(&mut JsonValue<'a>) -> ControlFlow<()>,
+    {
+        f(self)?;
+
+        match self {
+            JsonValue::Object(properties) => {
+                for property in properties.iter_mut() {
+                    property.value.visit_mut_with(f)?;
+                }
+            }
+            JsonValue::Array(json_values) => {
+                for value in json_values.iter_mut() {
+                    value.visit_mut_with(f)?;
+                }
+            }
+            _ => (),
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Returns a copy of this value with every number rewritten to its canonical form.
+    ///
+    /// Numbers are stored as `f64`, so spellings such as `1e2` and `100` or `1.0` and `1`
+    /// already parse to the same value. The remaining difference is the sign of zero,
+    /// which is folded so that `-0` and `0` render identically.
+    pub fn normalize_numbers(&self, bump: &'a Bump) -> JsonValue<'a> {
+        match self {
+            JsonValue::Number(val) if *val == 0.0 => JsonValue::Number(0.0),
+            JsonValue::Object(properties) => {
+                let mut items = Vec::with_capacity_in(properties.len(), bump);
+
+                items.extend(
+                    properties.iter().map(|property| {
+                        property.with_value(property.value.normalize_numbers(bump))
+                    }),
+                );
+
+                JsonValue::Object(items)
+            }
+            JsonValue::Array(json_values) => {
+                let mut items = Vec::with_capacity_in(json_values.len(), bump);
+
+                items.extend(
+                    json_values
+                        .iter()
+                        .map(|value| value.normalize_numbers(bump)),
+                );
+
+                JsonValue::Array(items)
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns a copy of this value with the value of every property whose key is in `keys`
+    /// replaced by the string `"***"`, at any depth.
+    ///
+    /// Matching properties are masked whole, so an object or array under a redacted key is
+    /// hidden entirely rather than searched.
+    pub fn redact_keys(&self, keys: &HashSet<&str>, bump: &'a Bump) -> JsonValue<'a> {
+        match self {
+            JsonValue::Object(properties) => {
+                let mut items = Vec::with_capacity_in(properties.len(), bump);
+
+                items.extend(properties.iter().map(|property| {
+                    property.with_value(if keys.contains(property.key_str()) {
+                        JsonValue::String("***")
                     } else {
-                        format!("{}.{}", prefix, property.key)
-                    };
+                        property.value.redact_keys(keys, bump)
+                    })
+                }));
+
+                JsonValue::Object(items)
+            }
+            JsonValue::Array(json_values) => {
+                let mut items = Vec::with_capacity_in(json_values.len(), bump);
+
+                items.extend(
+                    json_values
+                        .iter()
+                        .map(|value| value.redact_keys(keys, bump)),
+                );
+
+                JsonValue::Array(items)
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Compares two values treating both object properties and array elements as unordered,
+    /// recursively.
+    ///
+    /// Repeated keys and elements must appear the same number of times on both sides.
+    pub fn equivalent_unordered(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::Object(properties), JsonValue::Object(other_properties)) => {
+                same_multiset(properties, other_properties, |a, b| {
+                    a.key == b.key && a.value.equivalent_unordered(&b.value)
+                })
+            }
+            (JsonValue::Array(json_values), JsonValue::Array(other_values)) => {
+                same_multiset(json_values, other_values, |a, b| a.equivalent_unordered(b))
+            }
+            _ => self == other,
+        }
+    }
+}
+
+/// Pairs every item of `a` with a distinct equivalent item of `b`. Matching greedily is enough
+/// because `eq` is an equivalence relation.
+fn same_multiset<T, U>(a: &[T], b: &[U], eq: impl Fn(&T, &U) -> bool) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut matched = std::vec![false; b.len()];
+
+    a.iter().all(|item| {
+        b.iter()
+            .zip(matched.iter_mut())
+            .find(|(other, used)| !**used && eq(item, other))
+            .map(|(_, used)| *used = true)
+            .is_some()
+    })
+}
+
+/// Looks up the property `key` of an object, with the last occurrence of a repeated key
+/// winning.
+///
+/// # Panics
+///
+/// Panics if the value isn't an object or has no property `key`. Use [`JsonValue::get`] when
+/// either may happen.
+impl<'a> Index<&str> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, key: &str) -> &JsonValue<'a> {
+        match self.get(key) {
+            Some(value) => value,
+            None => missing_key(self, key),
+        }
+    }
+}
+
+impl<'a> IndexMut<&str> for JsonValue<'a> {
+    fn index_mut(&mut self, key: &str) -> &mut JsonValue<'a> {
+        if self.get(key).is_none() {
+            missing_key(self, key);
+        }
+
+        self.get_mut(key).unwrap()
+    }
+}
 
-                    property.value.flatten(&new_prefix, res);
+/// Looks up the element at `index` of an array.
+///
+/// # Panics
+///
+/// Panics if the value isn't an array or `index` is out of bounds. Use
+/// [`JsonValue::as_array`] and [`slice::get`] when either may happen.
+impl<'a> Index<usize> for JsonValue<'a> {
+    type Output = JsonValue<'a>;
+
+    fn index(&self, index: usize) -> &JsonValue<'a> {
+        match self {
+            JsonValue::Array(json_values) => &json_values[index],
+            _ => panic!("cannot index into {} with {index}", self.type_name()),
+        }
+    }
+}
+
+impl<'a> IndexMut<usize> for JsonValue<'a> {
+    fn index_mut(&mut self, index: usize) -> &mut JsonValue<'a> {
+        match self {
+            JsonValue::Array(json_values) => &mut json_values[index],
+            _ => panic!("cannot index into {} with {index}", self.type_name()),
+        }
+    }
+}
+
+#[cold]
+#[track_caller]
+fn missing_key(value: &JsonValue, key: &str) -> ! {
+    match value {
+        JsonValue::Object(_) => panic!("no property {key:?} in object"),
+        _ => panic!("cannot index into {} with {key:?}", value.type_name()),
+    }
+}
+
+impl JsonValue<'_> {
+    /// Writes the value as compact JSON with the default [`SerializeOptions`].
+    ///
+    /// Only `"`, `\` and control characters are escaped in strings and keys, using the short
+    /// forms such as `\n` where JSON has them.
+    /// JSON has no representation for non-finite numbers, so they are an error, see
+    /// [`JsonValue::write_json_with`] to write them some other way.
+    pub fn write_json<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_json_with(w, &SerializeOptions::default())
+    }
+
+    /// Writes the value as compact JSON like [`JsonValue::write_json`], handling non-finite
+    /// numbers as `options` says.
+    pub fn write_json_with<W: Write>(
+        &self,
+        w: &mut W,
+        options: &SerializeOptions,
+    ) -> io::Result<()> {
+        match self {
+            JsonValue::Object(properties) => {
+                w.write_all(b"{")?;
+
+                for (index, property) in properties.iter().enumerate() {
+                    if index > 0 {
+                        w.write_all(b",")?;
+                    }
+
+                    write_string(w, property.key_str(), options)?;
+                    w.write_all(b":")?;
+                    property.value.write_json_with(w, options)?;
                 }
+
+                w.write_all(b"}")
             }
             JsonValue::Array(json_values) => {
+                w.write_all(b"[")?;
+
                 for (index, value) in json_values.iter().enumerate() {
-                    let index = format!("{:03}", index);
+                    if index > 0 {
+                        w.write_all(b",")?;
+                    }
 
-                    let new_prefix = if prefix.is_empty() {
-                        index
-                    } else {
-                        format!("{}.{}", prefix, index)
-                    };
+                    value.write_json_with(w, options)?;
+                }
+
+                w.write_all(b"]")
+            }
+            _ => write_scalar(w, self, options),
+        }
+    }
+
+    /// Writes the value as multi-line JSON with `indent` spaces per level, failing on
+    /// non-finite numbers like [`JsonValue::write_json`].
+    ///
+    /// Empty objects and arrays stay on one line as `{}` and `[]`.
+    pub fn write_json_pretty<W: Write>(&self, w: &mut W, indent: usize) -> io::Result<()> {
+        self.write_json_pretty_with(w, Indent::Spaces(indent))
+    }
+
+    /// Writes the value as multi-line JSON like [`JsonValue::write_json_pretty`], indenting
+    /// each level with `indent`, such as tabs.
+    pub fn write_json_pretty_with<W: Write>(&self, w: &mut W, indent: Indent) -> io::Result<()> {
+        self.write_json_pretty_with_options(w, indent, &SerializeOptions::default())
+    }
+
+    /// Writes the value as multi-line JSON like [`JsonValue::write_json_pretty_with`],
+    /// serializing strings and numbers as `options` say.
+    pub fn write_json_pretty_with_options<W: Write>(
+        &self,
+        w: &mut W,
+        indent: Indent,
+        options: &SerializeOptions,
+    ) -> io::Result<()> {
+        self.write_pretty(w, indent, options, 0)
+    }
+
+    fn write_pretty<W: Write>(
+        &self,
+        w: &mut W,
+        indent: Indent,
+        options: &SerializeOptions,
+        level: usize,
+    ) -> io::Result<()> {
+        match self {
+            JsonValue::Object(properties) if !properties.is_empty() => {
+                w.write_all(b"{\n")?;
+
+                for (index, property) in properties.iter().enumerate() {
+                    if index > 0 {
+                        w.write_all(b",\n")?;
+                    }
+
+                    write_level(w, indent, level + 1)?;
+                    write_string(w, property.key_str(), options)?;
+                    w.write_all(b": ")?;
+                    property.value.write_pretty(w, indent, options, level + 1)?;
+                }
 
-                    value.flatten(&new_prefix, res);
+                w.write_all(b"\n")?;
+                write_level(w, indent, level)?;
+                w.write_all(b"}")
+            }
+            JsonValue::Array(json_values) if !json_values.is_empty() => {
+                w.write_all(b"[\n")?;
+
+                for (index, value) in json_values.iter().enumerate() {
+                    if index > 0 {
+                        w.write_all(b",\n")?;
+                    }
+
+                    write_level(w, indent, level + 1)?;
+                    value.write_pretty(w, indent, options, level + 1)?;
                 }
+
+                w.write_all(b"\n")?;
+                write_level(w, indent, level)?;
+                w.write_all(b"]")
             }
+            _ => self.write_json_with(w, options),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use bumpalo::{Bump, vec};
+    /// Writes the value starting `column` bytes into the line, followed on the same line by
+    /// `trailing` bytes such as a `,`.
+    fn write_pretty_width<W: Write>(
+        &self,
+        w: &mut W,
+        layout: &WidthLayout<'_>,
+        level: usize,
+        column: usize,
+        trailing: usize,
+    ) -> io::Result<()> {
+        // Measuring every container means nested ones are measured again at each level,
+        // which is fine at the sizes that are worth reading
+        if column + self.serialized_len_with(layout.options)? + trailing <= layout.max_width {
+            return self.write_json_with(w, layout.options);
+        }
 
-    #[test]
-    fn root_object_flattened() {
-        let bump = Bump::new();
+        let start = layout.indent_width(level + 1);
 
-        let root = JsonValue::Object(vec![
-            in &bump;
-            JsonProperty::from(("name", JsonValue::String("John"))),
-            JsonProperty::from(("age", JsonValue::Number(30.0))),
-            JsonProperty::from(("isStudent", JsonValue::Boolean(false))),
-            JsonProperty::from((
-                "address",
-                JsonValue::Object(vec![
-                    in &bump;
-                    JsonProperty::from((
-                        "street",
-                        JsonValue::Object(vec![
-                            in &bump;
-                            JsonProperty::from((
-                                "number",
-                                JsonValue::Number(95.0),
-                            ))]),
-                    )),
-                    JsonProperty::from(("city", JsonValue::String("New York"))),
-                    JsonProperty::from(("zipcode", JsonValue::Null)),
-                ]),
-            )),
-            JsonProperty::from((
-                "courses",
-                JsonValue::Array(vec![
-                    in &bump;
-                    JsonValue::Object(vec![
-                        in &bump;
-                        JsonProperty::from(("courseName", JsonValue::String("Math"))),
-                        JsonProperty::from(("grade", JsonValue::String("A"))),
-                    ]),
-                    JsonValue::Object(vec![
-                        in &bump;
-                        JsonProperty::from(("courseName", JsonValue::String("Science"))),
-                        JsonProperty::from(("grade", JsonValue::String("B"))),
-                    ]),
-                ]),
-            )),
-            JsonProperty::from((
-                "preferences",
-                JsonValue::Object(vec![
-                    in &bump;
-                    JsonProperty::from(("notifications", JsonValue::Boolean(true))),
-                    JsonProperty::from(("theme", JsonValue::String("dark"))),
-                ]),
-            )),
-            JsonProperty::from((
-                "scores",
-                JsonValue::Array(vec![
-                    in &bump;
-                    JsonValue::Number(95.0),
-                    JsonValue::Number(88.0),
-                    JsonValue::Number(76.0),
-                ]),
-            )),
-            JsonProperty::from((
-                "metadata",
-                JsonValue::Object(vec![
-                    in &bump;
-                    JsonProperty::from(("createdAt", JsonValue::String("2023-10-01T12:34:56Z"))),
-                    JsonProperty::from(("updatedAt", JsonValue::String("2023-10-01T12:34:56Z"))),
-                ]),
-            )),
-        ]);
+        match self {
+            JsonValue::Object(properties) if !properties.is_empty() => {
+                w.write_all(b"{\n")?;
 
-        assert_eq!(
-            root.flattened(),
-            BTreeMap::from([
-                ("address.city".into(), "New York".into()),
-                ("address.street.number".into(), "95".into()),
-                ("address.zipcode".into(), "null".into()),
-                ("age".into(), "30".into()),
-                ("courses.000.courseName".into(), "Math".into()),
-                ("courses.000.grade".into(), "A".into()),
-                ("courses.001.courseName".into(), "Science".into()),
-                ("courses.001.grade".into(), "B".into()),
-                ("isStudent".into(), "false".into()),
-                ("metadata.createdAt".into(), "2023-10-01T12:34:56Z".into()),
-                ("metadata.updatedAt".into(), "2023-10-01T12:34:56Z".into()),
-                ("name".into(), "John".into()),
-                ("preferences.notifications".into(), "true".into()),
-                ("preferences.theme".into(), "dark".into()),
-                ("scores.000".into(), "95".into()),
-                ("scores.001".into(), "88".into()),
-                ("scores.002".into(), "76".into()),
-            ])
-        );
+                for (index, property) in properties.iter().enumerate() {
+                    if index > 0 {
+                        w.write_all(b",\n")?;
+                    }
+
+                    let key = property.key_str();
+                    let column = start + string_len(key, layout.options) + 2;
+                    let trailing = usize::from(index + 1 < properties.len());
+
+                    write_level(w, layout.indent, level + 1)?;
+                    write_string(w, key, layout.options)?;
+                    w.write_all(b": ")?;
+                    property
+                        .value
+                        .write_pretty_width(w, layout, level + 1, column, trailing)?;
+                }
+
+                w.write_all(b"\n")?;
+                write_level(w, layout.indent, level)?;
+                w.write_all(b"}")
+            }
+            JsonValue::Array(json_values) if !json_values.is_empty() => {
+                w.write_all(b"[\n")?;
+
+                for (index, value) in json_values.iter().enumerate() {
+                    if index > 0 {
+                        w.write_all(b",\n")?;
+                    }
+
+                    let trailing = usize::from(index + 1 < json_values.len());
+
+                    write_level(w, layout.indent, level + 1)?;
+                    value.write_pretty_width(w, layout, level + 1, start, trailing)?;
+                }
+
+                w.write_all(b"\n")?;
+                write_level(w, layout.indent, level)?;
+                w.write_all(b"]")
+            }
+            _ => self.write_json_with(w, layout.options),
+        }
     }
 
-    #[test]
-    fn root_array_flattened() {
-        let bump = Bump::new();
+    /// Serializes the value as compact JSON with the default [`SerializeOptions`], failing
+    /// like [`JsonValue::write_json`] if it contains a non-finite number, such as a number too
+    /// large for an `f64` that parsed as infinite.
+    pub fn to_json_string(&self) -> io::Result<String> {
+        self.to_json_string_with(&SerializeOptions::default())
+    }
 
-        let root = JsonValue::Array(vec![
-            in &bump;
-            JsonValue::Object(vec![
-                in &bump;
-                JsonProperty::from(("albumId", JsonValue::Number(1.0))),
-                JsonProperty::from(("id", JsonValue::Number(1.0))),
-                JsonProperty::from((
-                    "title",
-                    JsonValue::String("accusamus beatae ad facilis cum similique qui sunt"),
-                )),
-                JsonProperty::from((
-                    "url",
-                    JsonValue::String("https://via.placeholder.com/600/92c952"),
-                )),
-                JsonProperty::from((
-                    "thumbnailUrl",
-                    JsonValue::String("https://via.placeholder.com/150/92c952"),
-                )),
-            ]),
-            JsonValue::Object(vec![
-                in &bump;
-                JsonProperty::from(("albumId", JsonValue::Number(1.0))),
-                JsonProperty::from(("id", JsonValue::Number(2.0))),
-                JsonProperty::from((
-                    "title",
-                    JsonValue::String("reprehenderit est deserunt velit ipsam"),
-                )),
-                JsonProperty::from((
-                    "url",
-                    JsonValue::String("https://via.placeholder.com/600/771796"),
-                )),
-                JsonProperty::from((
-                    "thumbnailUrl",
-                    JsonValue::String("https://via.placeholder.com/150/771796"),
-                )),
-            ]),
-        ]);
+    /// Serializes the value like [`JsonValue::write_json_with`], failing only when `options`
+    /// reject a non-finite number.
+    pub fn to_json_string_with(&self, options: &SerializeOptions) -> io::Result<String> {
+        let mut buf = std::vec::Vec::new();
 
-        assert_eq!(
-            root.flattened(),
-            BTreeMap::from([
-                ("000.albumId".into(), "1".into()),
-                ("000.id".into(), "1".into()),
-                (
-                    "000.thumbnailUrl".into(),
-                    "https://via.placeholder.com/150/92c952".into()
-                ),
-                (
-                    "000.title".into(),
-                    "accusamus beatae ad facilis cum similique qui sunt".into()
-                ),
-                (
-                    "000.url".into(),
-                    "https://via.placeholder.com/600/92c952".into()
-                ),
-                ("001.albumId".into(), "1".into()),
-                ("001.id".into(), "2".into()),
-                (
-                    "001.thumbnailUrl".into(),
-                    "https://via.placeholder.com/150/771796".into()
-                ),
-                (
-                    "001.title".into(),
-                    "reprehenderit est deserunt velit ipsam".into()
-                ),
-                (
-                    "001.url".into(),
-                    "https://via.placeholder.com/600/771796".into()
-                ),
-            ])
+        self.write_json_with(&mut buf, options)?;
+
+        Ok(String::from_utf8(buf).expect("serialized JSON is always valid UTF-8"))
+    }
+
+    /// Returns the number of bytes [`JsonValue::to_json_string`] would produce, without
+    /// building the string, e.g. to size an output buffer up front. Fails in the same cases.
+    pub fn serialized_len(&self) -> io::Result<usize> {
+        self.serialized_len_with(&SerializeOptions::default())
+    }
+
+    fn serialized_len_with(&self, options: &SerializeOptions) -> io::Result<usize> {
+        let mut counter = ByteCounter(0);
+
+        self.write_json_with(&mut counter, options)?;
+
+        Ok(counter.0)
+    }
+
+    /// Pretty-prints the value with `indent` spaces per level, failing on non-finite numbers
+    /// like [`JsonValue::to_json_string`].
+    pub fn to_json_pretty(&self, indent: usize) -> io::Result<String> {
+        self.to_json_pretty_with(Indent::Spaces(indent))
+    }
+
+    /// Pretty-prints the value like [`JsonValue::to_json_pretty`], indenting each level with
+    /// `indent`.
+    pub fn to_json_pretty_with(&self, indent: Indent) -> io::Result<String> {
+        self.to_json_pretty_with_options(indent, &SerializeOptions::default())
+    }
+
+    /// Pretty-prints the value like [`JsonValue::write_json_pretty_with_options`], failing
+    /// only when `options` reject a non-finite number.
+    pub fn to_json_pretty_with_options(
+        &self,
+        indent: Indent,
+        options: &SerializeOptions,
+    ) -> io::Result<String> {
+        let mut buf = std::vec::Vec::new();
+
+        self.write_json_pretty_with_options(&mut buf, indent, options)?;
+
+        Ok(String::from_utf8(buf).expect("serialized JSON is always valid UTF-8"))
+    }
+
+    /// Pretty-prints like [`JsonValue::to_json_pretty_with`], but writes any object or array
+    /// that fits as compact JSON on one line, ending at most `max_width` bytes from the start
+    /// of the line, with a tab counting as one. Larger ones are expanded, with their contents
+    /// fitted in turn.
+    pub fn to_json_pretty_width(&self, indent: Indent, max_width: usize) -> io::Result<String> {
+        self.to_json_pretty_width_with_options(indent, max_width, &SerializeOptions::default())
+    }
+
+    /// Pretty-prints like [`JsonValue::to_json_pretty_width`], serializing strings and numbers
+    /// as `options` say and failing only when they reject a non-finite number.
+    pub fn to_json_pretty_width_with_options(
+        &self,
+        indent: Indent,
+        max_width: usize,
+        options: &SerializeOptions,
+    ) -> io::Result<String> {
+        let mut buf = std::vec::Vec::new();
+
+        let layout = WidthLayout {
+            indent,
+            max_width,
+            options,
+        };
+
+        self.write_pretty_width(&mut buf, &layout, 0, 0, 0)?;
+
+        Ok(String::from_utf8(buf).expect("serialized JSON is always valid UTF-8"))
+    }
+}
+
+/// What stays the same throughout a call to [`JsonValue::to_json_pretty_width`].
+struct WidthLayout<'o> {
+    indent: Indent,
+    /// Maximum length of a line in bytes, counting a tab as one.
+    max_width: usize,
+    options: &'o SerializeOptions,
+}
+
+impl WidthLayout<'_> {
+    /// Returns the number of bytes [`write_level`] writes for `level`.
+    fn indent_width(&self, level: usize) -> usize {
+        match self.indent {
+            Indent::Spaces(width) => width * level,
+            Indent::Tab => level,
+        }
+    }
+}
+
+/// A writer that discards its input and only counts the bytes, so lengths always agree with
+/// what the serializer writes.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the number of bytes `val` takes as a quoted, escaped JSON string.
+fn string_len(val: &str, options: &SerializeOptions) -> usize {
+    let mut counter = ByteCounter(0);
+
+    write_string(&mut counter, val, options).expect("counting bytes never fails");
+
+    counter.0
+}
+
+fn write_scalar<W: Write>(
+    w: &mut W,
+    value: &JsonValue,
+    options: &SerializeOptions,
+) -> io::Result<()> {
+    match value {
+        JsonValue::Null => w.write_all(b"null"),
+        JsonValue::Boolean(val) => write!(w, "{val}"),
+        JsonValue::Number(val) if !val.is_finite() => match options.non_finite {
+            NonFinite::Error => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{val} can't be written as JSON"),
+            )),
+            NonFinite::Null => w.write_all(b"null"),
+            NonFinite::Json5Literal => w.write_all(format_number(*val).as_bytes()),
+        },
+        JsonValue::Number(val) => w.write_all(format_number(*val).as_bytes()),
+        JsonValue::String(val) => write_string(w, val, options),
+        JsonValue::Object(_) | JsonValue::Array(_) => unreachable!("not a scalar"),
+    }
+}
+
+/// Renders a number the same way everywhere it is turned into text, using the shortest
+/// representation that round-trips. Non-finite numbers become the JSON5 literals `NaN`,
+/// `Infinity` and `-Infinity`, so callers writing JSON must decide what to do with them first.
+pub(crate) fn format_number(val: f64) -> String {
+    if val.is_nan() {
+        "NaN".to_string()
+    } else if val.is_infinite() {
+        if val > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        val.to_string()
+    }
+}
+
+/// Writes `val` as a quoted JSON string, escaping what JSON requires and, if `options` say so,
+/// `/` as well.
+fn write_string<W: Write>(w: &mut W, val: &str, options: &SerializeOptions) -> io::Result<()> {
+    w.write_all(b"\"")?;
+
+    let bytes = val.as_bytes();
+    let mut written = 0;
+
+    // Every byte that needs escaping is ASCII, so runs between them are whole characters
+    for (index, &byte) in bytes.iter().enumerate() {
+        let escape: &[u8] = match byte {
+            b'"' => b"\\\"",
+            b'\\' => b"\\\\",
+            b'\n' => b"\\n",
+            b'\r' => b"\\r",
+            b'\t' => b"\\t",
+            0x08 => b"\\b",
+            0x0C => b"\\f",
+            b'/' if options.escape_forward_slash => b"\\/",
+            0x00..=0x1F => {
+                w.write_all(&bytes[written..index])?;
+                write!(w, "\\u{byte:04x}")?;
+                written = index + 1;
+                continue;
+            }
+            _ => continue,
+        };
+
+        w.write_all(&bytes[written..index])?;
+        w.write_all(escape)?;
+        written = index + 1;
+    }
+
+    w.write_all(&bytes[written..])?;
+    w.write_all(b"\"")
+}
+
+fn write_indent<W: Write>(w: &mut W, width: usize) -> io::Result<()> {
+    write!(w, "{:width$}", "")
+}
+
+fn write_level<W: Write>(w: &mut W, indent: Indent, level: usize) -> io::Result<()> {
+    match indent {
+        Indent::Spaces(width) => write_indent(w, width * level),
+        Indent::Tab => (0..level).try_for_each(|_| w.write_all(b"\t")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn normalize_numbers_canonical_form() {
+        let bump = Bump::new();
+
+        let root = Parser::new(
+            r#"{"exponent": 1e2, "fraction": 1.0, "zero": -0, "nested": [1E+2, -0.0]}"#,
+        )
+        .parse(&bump)
+        .unwrap();
+        let expected =
+            Parser::new(r#"{"exponent": 100, "fraction": 1, "zero": 0, "nested": [100, 0]}"#)
+                .parse(&bump)
+                .unwrap();
+
+        let normalized = root.normalize_numbers(&bump);
+
+        assert_eq!(normalized, expected);
+        assert_eq!(normalized.flattened(), expected.flattened());
+        assert_eq!(
+            normalized.flattened(),
+            BTreeMap::from([
+                ("exponent".into(), "100".into()),
+                ("fraction".into(), "1".into()),
+                ("nested.000".into(), "100".into()),
+                ("nested.001".into(), "0".into()),
+                ("zero".into(), "0".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn redact_nested_keys() {
+        let bump = Bump::new();
+        let root = Parser::new(
+            r#"{"user": {"name": "John", "password": "hunter2", "sessions": [{"token": {"id": 1}, "ip": "10.0.0.1"}]}, "password_hint": "pet"}"#,
+        )
+        .parse(&bump)
+        .unwrap();
+
+        let redacted = root.redact_keys(&HashSet::from(["password", "token"]), &bump);
+
+        assert_eq!(
+            redacted,
+            Parser::new(
+                r#"{"user": {"name": "John", "password": "***", "sessions": [{"token": "***", "ip": "10.0.0.1"}]}, "password_hint": "pet"}"#,
+            )
+            .parse(&bump)
+            .unwrap()
+        );
+        assert_eq!(root.redact_keys(&HashSet::new(), &bump), root);
+    }
+
+    #[test]
+    fn equivalent_unordered_arrays() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let root = parse(
+            r#"{"ids": [3, 1, 2], "users": [{"name": "a", "roles": ["x", "y"]}, {"name": "b"}]}"#,
+        );
+        let shuffled = parse(
+            r#"{"users": [{"name": "b"}, {"roles": ["y", "x"], "name": "a"}], "ids": [1, 2, 3]}"#,
+        );
+
+        assert!(root.equivalent_unordered(&shuffled));
+        assert!(shuffled.equivalent_unordered(&root));
+        assert_ne!(root, shuffled);
+
+        assert!(parse("[1, 1, 2]").equivalent_unordered(&parse("[2, 1, 1]")));
+        assert!(!parse("[1, 1, 2]").equivalent_unordered(&parse("[1, 2, 2]")));
+        assert!(!parse("[1, 2]").equivalent_unordered(&parse("[1, 2, 2]")));
+        assert!(!parse(r#"{"a": [1]}"#).equivalent_unordered(&parse(r#"{"a": 1}"#)));
+    }
+
+    #[test]
+    fn visit_mut_stops_after_first_match() {
+        let bump = Bump::new();
+        let mut root = Parser::new(
+            r#"{"short": "ok", "items": [1, {"bio": "a very long biography"}, "another long string"]}"#,
+        )
+        .parse(&bump)
+        .unwrap();
+
+        let mut visited = 0;
+
+        let flow = root.visit_mut(|value| {
+            visited += 1;
+
+            match value {
+                JsonValue::String(val) if val.len() > 10 => {
+                    *val = &val[..10];
+                    ControlFlow::Break(())
+                }
+                _ => ControlFlow::Continue(()),
+            }
+        });
+
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert_eq!(visited, 6);
+        assert_eq!(
+            root,
+            Parser::new(
+                r#"{"short": "ok", "items": [1, {"bio": "a very lon"}, "another long string"]}"#
+            )
+            .parse(&bump)
+            .unwrap()
+        );
+        assert_eq!(
+            root.visit_mut(|_| ControlFlow::Continue(())),
+            ControlFlow::Continue(())
+        );
+    }
+
+    #[test]
+    fn numbers_render_consistently() {
+        let bump = Bump::new();
+        let root = Parser::new("[0.30000000000000004, 1e21, 1.5e-7, -0, 1e999]")
+            .parse(&bump)
+            .unwrap();
+
+        let flattened = root.flattened().into_values().collect::<std::vec::Vec<_>>();
+
+        assert_eq!(
+            flattened,
+            [
+                "0.30000000000000004",
+                "1000000000000000000000",
+                "0.00000015",
+                "-0",
+                "Infinity",
+            ]
+        );
+
+        let json5 = SerializeOptions {
+            non_finite: NonFinite::Json5Literal,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            root.to_json_string_with(&json5).unwrap(),
+            format!("[{}]", flattened.join(","))
+        );
+    }
+
+    #[test]
+    fn present_null_differs_from_absent() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let set_null = parse(r#"{"a": null}"#);
+        let absent = parse("{}");
+
+        assert_eq!(set_null.get_present("a"), Some(&JsonValue::Null));
+        assert_eq!(absent.get_present("a"), None);
+        assert_eq!(
+            parse(r#"{"a": 1, "a": null}"#).get_present("a"),
+            Some(&JsonValue::Null)
+        );
+        assert_eq!(parse(r#"[null]"#).get_present("0"), None);
+
+        for (json, key) in [(r#"{"a": null}"#, "a"), ("{}", "a"), ("[null]", "0")] {
+            let value = parse(json);
+
+            assert_eq!(value.get_present(key), value.get(key));
+        }
+    }
+
+    #[test]
+    fn index_by_key_and_position() {
+        let bump = Bump::new();
+        let mut root = Parser::new(r#"{"address": {"city": "Leeds"}, "tags": ["a", "b"]}"#)
+            .parse(&bump)
+            .unwrap();
+
+        assert_eq!(root["address"]["city"], JsonValue::String("Leeds"));
+        assert_eq!(root["tags"][1], JsonValue::String("b"));
+        assert_eq!(root.get("missing"), None);
+        assert_eq!(root["tags"].get("0"), None);
+
+        root["tags"][0] = JsonValue::Null;
+        root["address"]["city"] = JsonValue::Number(1.0);
+
+        assert_eq!(
+            root.to_json_string().unwrap(),
+            r#"{"address":{"city":1},"tags":[null,"b"]}"#
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no property \"city\" in object")]
+    fn index_missing_key() {
+        let bump = Bump::new();
+        let root = Parser::new(r#"{"address": {}}"#).parse(&bump).unwrap();
+
+        let _ = &root["address"]["city"];
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index into array with \"city\"")]
+    fn index_wrong_type() {
+        let bump = Bump::new();
+        let mut root = Parser::new("[]").parse(&bump).unwrap();
+
+        root["city"] = JsonValue::Null;
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn index_out_of_bounds() {
+        let bump = Bump::new();
+        let root = Parser::new("[1, 2]").parse(&bump).unwrap();
+
+        let _ = &root[2];
+    }
+
+    #[test]
+    fn leaf_count_matches_flattened() {
+        for input in [
+            include_str!("../../test_data/github_events.json"),
+            include_str!("../../test_data/log.json"),
+            r#"{"a": [], "b": {}, "c": [null, [true, {"d": "e"}]]}"#,
+        ] {
+            let bump = Bump::new();
+            let root = Parser::new(input).parse(&bump).unwrap();
+
+            assert_eq!(root.leaf_count(), root.flattened().len());
+        }
+
+        assert_eq!(JsonValue::Number(1.0).leaf_count(), 1);
+    }
+
+    #[test]
+    fn all_keys_across_objects() {
+        let bump = Bump::new();
+        let root = Parser::new(
+            r#"[{"id": 1, "name": "a"}, {"id": 2, "tags": [{"label": "x"}]}, [{"name": null}], 3]"#,
+        )
+        .parse(&bump)
+        .unwrap();
+
+        assert_eq!(
+            root.all_keys(),
+            BTreeSet::from(["id", "label", "name", "tags"].map(String::from))
+        );
+        assert!(
+            JsonValue::Array(bumpalo::vec![in &bump])
+                .all_keys()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn key_type_profile_inconsistent_types() {
+        let bump = Bump::new();
+        let root = Parser::new(
+            r#"[{"id": 1, "name": "a"}, {"id": "2", "tags": []}, {"id": 3, "name": null}, 4]"#,
+        )
+        .parse(&bump)
+        .unwrap();
+
+        assert_eq!(
+            root.key_type_profile(),
+            BTreeMap::from([
+                ("id".to_owned(), BTreeSet::from(["number", "string"])),
+                ("name".to_owned(), BTreeSet::from(["null", "string"])),
+                ("tags".to_owned(), BTreeSet::from(["array"])),
+            ])
+        );
+        assert!(
+            Parser::new(r#"{"id": 1}"#)
+                .parse(&bump)
+                .unwrap()
+                .key_type_profile()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn array_common_keys_intersection() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let rows = parse(
+            r#"[
+                {"id": 1, "name": "a", "email": "a@x", "id": 5},
+                {"name": "b", "phone": "123", "id": 2},
+                {"id": 3, "admin": true, "name": null, "email": "c@x"}
+            ]"#,
+        );
+
+        assert_eq!(rows.array_common_keys(), Some(vec!["id", "name"]));
+        assert_eq!(parse("[]").array_common_keys(), Some(vec![]));
+        assert_eq!(parse(r#"[{"a": 1}, 2]"#).array_common_keys(), None);
+        assert_eq!(parse(r#"{"a": 1}"#).array_common_keys(), None);
+    }
+
+    #[test]
+    fn typed_scalar_vecs() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        assert_eq!(
+            parse("[1, -2.5, 3e2]").as_f64_vec(),
+            Some(vec![1.0, -2.5, 300.0])
+        );
+        assert_eq!(parse(r#"["a", "b"]"#).as_str_vec(), Some(vec!["a", "b"]));
+        assert_eq!(
+            parse("[true, false]").as_bool_vec(),
+            Some(vec![true, false])
+        );
+        assert_eq!(parse("[]").as_f64_vec(), Some(vec![]));
+
+        let mixed = parse(r#"[1, "2", 3]"#);
+
+        assert_eq!(mixed.as_f64_vec(), None);
+        assert_eq!(mixed.as_str_vec(), None);
+        assert_eq!(parse("[1, null]").as_f64_vec(), None);
+        assert_eq!(parse("[[1], [2]]").as_f64_vec(), None);
+
+        assert_eq!(parse(r#"{"a": 1}"#).as_f64_vec(), None);
+        assert_eq!(parse("1").as_f64_vec(), None);
+        assert_eq!(parse(r#""a""#).as_str_vec(), None);
+    }
+
+    #[test]
+    fn is_flat() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        assert!(parse(r#"{"id": 1, "name": "a", "ok": true, "x": null}"#).is_flat());
+        assert!(parse(r#"[1, "two", false]"#).is_flat());
+        assert!(parse("{}").is_flat());
+        assert!(parse("3").is_flat());
+        assert!(!parse(r#"{"id": 1, "tags": {"a": 1}}"#).is_flat());
+        assert!(!parse(r#"{"id": 1, "tags": []}"#).is_flat());
+        assert!(!parse("[1, [2]]").is_flat());
+    }
+
+    #[test]
+    fn entries_sorted_leaves_order() {
+        let bump = Bump::new();
+        let root = Parser::new(r#"{"b": 1, "a": [2], "c": null, "a": 3}"#)
+            .parse(&bump)
+            .unwrap();
+
+        let sorted = root.entries_sorted();
+
+        assert_eq!(
+            sorted
+                .iter()
+                .map(|&(key, _)| key)
+                .collect::<std::vec::Vec<_>>(),
+            ["a", "a", "b", "c"]
+        );
+        assert_eq!(sorted[1].1, &JsonValue::Number(3.0));
+        assert_eq!(
+            root.as_object()
+                .unwrap()
+                .iter()
+                .map(|property| property.key_str())
+                .collect::<std::vec::Vec<_>>(),
+            ["b", "a", "c", "a"]
+        );
+        assert!(JsonValue::Number(1.0).entries_sorted().is_empty());
+    }
+
+    #[test]
+    fn non_finite_policies() {
+        let bump = Bump::new();
+        let value = JsonValue::Array(bumpalo::vec![
+            in &bump;
+            JsonValue::Number(f64::NAN),
+            JsonValue::Number(f64::INFINITY),
+            JsonValue::Number(f64::NEG_INFINITY),
+            JsonValue::Number(1.5),
+        ]);
+        let with = |non_finite| {
+            value.to_json_string_with(&SerializeOptions {
+                non_finite,
+                ..Default::default()
+            })
+        };
+
+        let err = with(NonFinite::Error).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "NaN can't be written as JSON");
+        assert!(
+            JsonValue::Number(f64::INFINITY)
+                .to_json_string_with(&SerializeOptions::default())
+                .is_err()
+        );
+        assert_eq!(with(NonFinite::Null).unwrap(), "[null,null,null,1.5]");
+        assert_eq!(
+            with(NonFinite::Json5Literal).unwrap(),
+            "[NaN,Infinity,-Infinity,1.5]"
+        );
+        assert!(value.write_json(&mut std::vec::Vec::new()).is_err());
+
+        let null = SerializeOptions {
+            non_finite: NonFinite::Null,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            value
+                .to_json_pretty_with_options(Indent::Spaces(1), &null)
+                .unwrap(),
+            "[\n null,\n null,\n null,\n 1.5\n]"
+        );
+        assert_eq!(
+            value
+                .to_json_pretty_width_with_options(Indent::Spaces(2), 80, &null)
+                .unwrap(),
+            "[null,null,null,1.5]"
+        );
+        assert!(
+            value
+                .write_json_pretty(&mut std::vec::Vec::new(), 2)
+                .is_err()
+        );
+        assert!(
+            value
+                .to_json_pretty_width_with_options(Indent::Spaces(2), 80, &Default::default())
+                .is_err()
+        );
+
+        let flattened = value.flattened_ordered();
+
+        assert_eq!(flattened[0].1, "NaN");
+        assert_eq!(flattened[1].1, "Infinity");
+        assert_eq!(flattened[2].1, "-Infinity");
+    }
+
+    #[test]
+    fn overflowing_number_fails_to_serialize() {
+        let bump = Bump::new();
+        let root = Parser::new("[1e400]").parse(&bump).unwrap();
+
+        assert_eq!(
+            root,
+            JsonValue::Array(bumpalo::vec![in &bump; JsonValue::Number(f64::INFINITY)])
+        );
+
+        for result in [
+            root.to_json_string(),
+            root.to_json_pretty(2),
+            root.to_json_pretty_with(Indent::Tab),
+            root.to_json_pretty_width(Indent::Spaces(2), 80),
+        ] {
+            assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+
+        assert!(root.serialized_len().is_err());
+    }
+
+    #[test]
+    fn escape_forward_slash_for_html() {
+        let json = r#"{"html": "<b>hi</b></script><script>alert(1)", "a/b": "x\/y\\/z"}"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+        let escaped = root
+            .to_json_string_with(&SerializeOptions {
+                escape_forward_slash: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(
+            escaped,
+            r#"{"html":"<b>hi<\/b><\/script><script>alert(1)","a\/b":"x\/y\\\/z"}"#
+        );
+        assert!(!escaped.contains("</"));
+        assert!(Parser::new(&escaped).parse(&bump).is_ok());
+        assert!(root.to_json_string().unwrap().contains("</script>"));
+    }
+
+    #[test]
+    fn serialized_len_matches_output() {
+        for input in [
+            include_str!("../../test_data/log.json"),
+            include_str!("../../test_data/github_events.json"),
+            include_str!("../../test_data/numbers.json"),
+            r#"{"escaped \"key\"": ["\u00e9\n", -0.0, 1e300, true, null, {}, []]}"#,
+        ] {
+            let bump = Bump::new();
+            let root = Parser::new(input).parse(&bump).unwrap();
+
+            assert_eq!(
+                root.serialized_len().unwrap(),
+                root.to_json_string().unwrap().len()
+            );
+        }
+    }
+
+    #[test]
+    fn pretty_width_keeps_short_containers_inline() {
+        let json = r#"{"id": 7, "point": [1, 2], "tags": ["alpha", "beta", "gamma", "delta"], "meta": {"a": {"b": true}}}"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+
+        assert_eq!(
+            root.to_json_pretty_width(Indent::Spaces(2), 30).unwrap(),
+            r#"{
+  "id": 7,
+  "point": [1,2],
+  "tags": [
+    "alpha",
+    "beta",
+    "gamma",
+    "delta"
+  ],
+  "meta": {"a":{"b":true}}
+}"#
+        );
+
+        // Every line, including the key in front of an inline value, fits the width
+        for line in root
+            .to_json_pretty_width(Indent::Spaces(2), 30)
+            .unwrap()
+            .lines()
+        {
+            assert!(line.len() <= 30, "{line:?} is too long");
+        }
+
+        assert_eq!(
+            root.to_json_pretty_width(Indent::Spaces(2), 200).unwrap(),
+            root.to_json_string().unwrap()
+        );
+        assert_eq!(
+            root.to_json_pretty_width(Indent::Spaces(2), 0).unwrap(),
+            root.to_json_pretty(2).unwrap()
+        );
+    }
+
+    #[test]
+    fn pretty_width_counts_commas_and_escaped_keys() {
+        // Each case is at the boundary: the inline container ends exactly at the width, and
+        // one byte less expands it
+        for (input, width, expected) in [
+            (
+                r#"{"a":[1,2],"b":1}"#,
+                13,
+                "{\n  \"a\": [1,2],\n  \"b\": 1\n}",
+            ),
+            (
+                r#"{"a":[1,2],"b":1}"#,
+                12,
+                "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": 1\n}",
+            ),
+            (r#"[[1,2],[3]]"#, 8, "[\n  [1,2],\n  [3]\n]"),
+            (r#"[[1,2],[3]]"#, 7, "[\n  [\n    1,\n    2\n  ],\n  [3]\n]"),
+            (
+                r#"{"k\n\n\n\n":[1,2],"b":1}"#,
+                21,
+                "{\n  \"k\\n\\n\\n\\n\": [1,2],\n  \"b\": 1\n}",
+            ),
+            (
+                r#"{"k\n\n\n\n":[1,2],"b":1}"#,
+                20,
+                "{\n  \"k\\n\\n\\n\\n\": [\n    1,\n    2\n  ],\n  \"b\": 1\n}",
+            ),
+        ] {
+            let bump = Bump::new();
+            let root = Parser::new(input).parse(&bump).unwrap();
+            let pretty = root.to_json_pretty_width(Indent::Spaces(2), width).unwrap();
+
+            assert_eq!(pretty, expected, "{input} at width {width}");
+
+            for line in pretty.lines() {
+                assert!(line.len() <= width, "{line:?} is longer than {width}");
+            }
+        }
+
+        // A tab counts as one byte
+        let bump = Bump::new();
+        let root = Parser::new("[[1,2],[3]]").parse(&bump).unwrap();
+
+        assert_eq!(
+            root.to_json_pretty_width(Indent::Tab, 7).unwrap(),
+            "[\n\t[1,2],\n\t[3]\n]"
+        );
+        assert_eq!(
+            root.to_json_pretty_width(Indent::Tab, 6).unwrap(),
+            "[\n\t[\n\t\t1,\n\t\t2\n\t],\n\t[3]\n]"
+        );
+        assert_eq!(
+            root.to_json_pretty_width(Indent::Tab, 0).unwrap(),
+            root.to_json_pretty_with(Indent::Tab).unwrap()
+        );
+    }
+
+    #[test]
+    fn unwrap_single_property_objects() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let wrapped = parse(r#"{"data": {"result": {"payload": [1, 2]}}}"#);
+
+        assert_eq!(wrapped.unwrap_single(), &parse("[1, 2]"));
+
+        let multi = parse(r#"{"data": {"result": {"a": 1, "b": 2}}}"#);
+
+        assert_eq!(multi.unwrap_single(), &parse(r#"{"a": 1, "b": 2}"#));
+        assert_eq!(parse(r#"{"a": {}}"#).unwrap_single(), &parse("{}"));
+        assert_eq!(parse("null").unwrap_single(), &JsonValue::Null);
+    }
+
+    #[test]
+    fn edit_in_place() {
+        let bump = Bump::new();
+        let mut root = Parser::new(r#"{"name": "John", "scores": [95, 88]}"#)
+            .parse(&bump)
+            .unwrap();
+
+        let properties = root.as_object_mut().unwrap();
+
+        properties[0].value = JsonValue::String("Jane");
+        properties[1]
+            .value
+            .as_array_mut()
+            .unwrap()
+            .push(JsonValue::Number(76.0));
+
+        assert!(root.as_array().is_none());
+        assert!(JsonValue::Null.as_object().is_none());
+        assert_eq!(
+            root,
+            Parser::new(r#"{"name": "Jane", "scores": [95, 88, 76]}"#)
+                .parse(&bump)
+                .unwrap()
+        );
+        assert_eq!(root.as_object().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn write_json_matches_string_methods() {
+        let json = r#"{"name": "Jo\"hn", "scores": [95, 1.5, -0.25], "empty": {}, "none": [], "nested": {"ok": true, "value": null}}"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+
+        let mut compact = std::vec::Vec::new();
+        let mut pretty = std::vec::Vec::new();
+
+        root.write_json(&mut compact).unwrap();
+        root.write_json_pretty(&mut pretty, 2).unwrap();
+
+        assert_eq!(
+            String::from_utf8(compact).unwrap(),
+            root.to_json_string().unwrap()
+        );
+        assert_eq!(
+            String::from_utf8(pretty).unwrap(),
+            root.to_json_pretty(2).unwrap()
+        );
+        assert_eq!(
+            root.to_json_string().unwrap(),
+            r#"{"name":"Jo\"hn","scores":[95,1.5,-0.25],"empty":{},"none":[],"nested":{"ok":true,"value":null}}"#
+        );
+        assert_eq!(
+            root.to_json_pretty(2).unwrap(),
+            r#"{
+  "name": "Jo\"hn",
+  "scores": [
+    95,
+    1.5,
+    -0.25
+  ],
+  "empty": {},
+  "none": [],
+  "nested": {
+    "ok": true,
+    "value": null
+  }
+}"#
+        );
+        assert_eq!(
+            Parser::new(&root.to_json_pretty(4).unwrap())
+                .parse(&bump)
+                .unwrap(),
+            root
+        );
+    }
+
+    #[test]
+    fn pretty_with_tabs() {
+        let bump = Bump::new();
+        let root = Parser::new(r#"{"a b": [1, {"c": null}], "d": {}}"#)
+            .parse(&bump)
+            .unwrap();
+
+        assert_eq!(
+            root.to_json_pretty_with(Indent::Tab).unwrap(),
+            "{\n\t\"a b\": [\n\t\t1,\n\t\t{\n\t\t\t\"c\": null\n\t\t}\n\t],\n\t\"d\": {}\n}"
+        );
+        assert_eq!(
+            root.to_json_pretty_with(Indent::Spaces(3)).unwrap(),
+            root.to_json_pretty(3).unwrap()
         );
     }
 }
@@ -0,0 +1,127 @@
+use std::{fmt::Display, fs, io, path::PathBuf};
+
+use bumpalo::Bump;
+
+use crate::{error::ExpectedTokenError, parser::Parser};
+
+/// Outcome of validating several documents, produced by [`validate_paths`].
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    pub total: usize,
+    pub ok: usize,
+    /// Documents that couldn't be read or failed to parse.
+    pub failed: usize,
+    pub errors: Vec<(PathBuf, ExpectedTokenError)>,
+    pub unreadable: Vec<(PathBuf, io::Error)>,
+}
+
+impl BatchReport {
+    fn record(&mut self, path: PathBuf, input: &str) {
+        self.total += 1;
+
+        let bump = Bump::new();
+
+        match Parser::new(input).parse(&bump) {
+            Ok(_) => self.ok += 1,
+            Err(e) => {
+                self.failed += 1;
+                self.errors.push((path, e));
+            }
+        }
+    }
+}
+
+impl Display for BatchReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "checked {} files: {} ok, {} failed",
+            self.total, self.ok, self.failed
+        )?;
+
+        for (path, e) in &self.unreadable {
+            write!(f, "\n  {}: {e}", path.display())?;
+        }
+
+        for (path, e) in &self.errors {
+            write!(f, "\n  {}: {e}", path.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads and parses every file in `paths`, collecting the failures into a [`BatchReport`].
+pub fn validate_paths<I>(paths: I) -> BatchReport
+where
+    I: IntoIterator,
+    I::Item: Into<PathBuf>,
+{
+    let mut report = BatchReport::default();
+
+    for path in paths {
+        let path = path.into();
+
+        match fs::read_to_string(&path) {
+            Ok(input) => report.record(path, &input),
+            Err(e) => {
+                report.total += 1;
+                report.failed += 1;
+                report.unreadable.push((path, e));
+            }
+        }
+    }
+
+    report
+}
+
+/// Same as [`validate_paths`] for documents that are already in memory, labelled with a path.
+pub fn validate_inputs<'i, I, P>(inputs: I) -> BatchReport
+where
+    I: IntoIterator<Item = (P, &'i str)>,
+    P: Into<PathBuf>,
+{
+    let mut report = BatchReport::default();
+
+    for (path, input) in inputs {
+        report.record(path.into(), input);
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_report_counts() {
+        let report = validate_inputs([
+            ("a.json", r#"{"ok": true}"#),
+            ("b.json", "[1, 2,]"),
+            ("c.json", "42"),
+            ("d.json", r#"{"a" 1}"#),
+        ]);
+
+        assert_eq!((report.total, report.ok, report.failed), (4, 2, 2));
+        assert_eq!(
+            report
+                .errors
+                .iter()
+                .map(|(path, e)| (path.to_str().unwrap(), e.invalid_col))
+                .collect::<Vec<_>>(),
+            [("b.json", 7), ("d.json", 6)]
+        );
+        assert_eq!(
+            report.to_string(),
+            "checked 4 files: 2 ok, 2 failed
+  b.json: expected a JSON value at row 1 column 7 but got ']' instead
+  d.json: expected ':' after object key at row 1 column 6 but got 'NUMBER' instead"
+        );
+
+        let missing = validate_paths(["/definitely/not/here.json"]);
+
+        assert_eq!((missing.total, missing.ok, missing.failed), (1, 0, 1));
+        assert_eq!(missing.unreadable.len(), 1);
+    }
+}
@@ -0,0 +1,128 @@
+use bumpalo::Bump;
+
+use crate::{ast::JsonValue, error::ExpectedTokenError, parser::Parser};
+
+/// A JSON value that owns all of its contents, with no arena or input lifetime.
+///
+/// Slower to build than [`JsonValue`] but can be kept and passed around freely. Strings and
+/// keys are decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoxedJson {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Object(Box<[(String, BoxedJson)]>),
+    Array(Box<[BoxedJson]>),
+}
+
+/// Parses `input` into a [`BoxedJson`] that doesn't borrow from it.
+pub fn parse_boxed(input: &str) -> Result<BoxedJson, ExpectedTokenError> {
+    let bump = Bump::new();
+    let value = Parser::new(input).parse(&bump)?;
+
+    Ok(BoxedJson::from_value(&value))
+}
+
+impl BoxedJson {
+    fn from_value(value: &JsonValue) -> Self {
+        match value {
+            JsonValue::Null => BoxedJson::Null,
+            JsonValue::Boolean(val) => BoxedJson::Boolean(*val),
+            JsonValue::Number(val) => BoxedJson::Number(*val),
+            JsonValue::String(val) => BoxedJson::String(val.to_string()),
+            JsonValue::Object(properties) => BoxedJson::Object(
+                properties
+                    .iter()
+                    .map(|property| {
+                        (
+                            property.key.to_string(),
+                            BoxedJson::from_value(&property.value),
+                        )
+                    })
+                    .collect(),
+            ),
+            JsonValue::Array(json_values) => BoxedJson::Array(
+                json_values
+                    .iter()
+                    .map(|value| BoxedJson::from_value(value))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns the value of the property `key` if this is an object containing it. When an
+    /// object repeats a key the last occurrence wins.
+    pub fn get(&self, key: &str) -> Option<&BoxedJson> {
+        match self {
+            BoxedJson::Object(properties) => properties
+                .iter()
+                .rev()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at `index` if this is an array long enough to have one.
+    pub fn index(&self, index: usize) -> Option<&BoxedJson> {
+        match self {
+            BoxedJson::Array(values) => values.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            BoxedJson::String(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            BoxedJson::Number(val) => Some(*val),
+            _ => None,
+        }
+    }
+}
+
+impl From<&JsonValue<'_>> for BoxedJson {
+    fn from(value: &JsonValue<'_>) -> Self {
+        BoxedJson::from_value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn navigate_after_input_dropped() {
+        let input = String::from(
+            r#"{"user": {"name": "Jo\"hn", "tags": ["admin", "ops"]}, "score": 9.5, "kéy": null}"#,
+        );
+        let value = parse_boxed(&input).unwrap();
+
+        drop(input);
+
+        let user = value.get("user").unwrap();
+
+        assert_eq!(user.get("name").and_then(BoxedJson::as_str), Some("Jo\"hn"));
+        assert_eq!(
+            user.get("tags").and_then(|tags| tags.index(1)),
+            Some(&BoxedJson::String("ops".to_owned()))
+        );
+        assert_eq!(user.get("tags").and_then(|tags| tags.index(2)), None);
+        assert_eq!(value.get("score").and_then(BoxedJson::as_f64), Some(9.5));
+        assert_eq!(value.get("kéy"), Some(&BoxedJson::Null));
+        assert_eq!(value.get("missing"), None);
+    }
+
+    #[test]
+    fn parse_boxed_error() {
+        let err = parse_boxed("[1, ]").unwrap_err();
+
+        assert_eq!((err.invalid_row, err.invalid_col), (1, 5));
+    }
+}
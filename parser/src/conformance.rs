@@ -0,0 +1,75 @@
+//! Runs the parser over cases from the JSONTestSuite parsing corpus
+//! (<https://github.com/nst/JSONTestSuite>) kept in `test_suite/`.
+//!
+//! Files prefixed `y_` must parse, `n_` must be rejected and `i_` are implementation
+//! defined, so each of those is listed in [`IMPLEMENTATION_DEFINED`] with our behaviour.
+
+use std::fs;
+
+use bumpalo::Bump;
+
+use crate::{options::ParserOptions, parser::Parser};
+
+/// Implementation defined cases and whether this parser accepts them.
+const IMPLEMENTATION_DEFINED: &[(&str, bool)] = &[
+    // Out of range numbers parse to `0`, `inf` or a rounded `f64`
+    ("i_number_double_huge_neg_exp.json", true),
+    ("i_number_real_overflow.json", true),
+    ("i_number_too_big_neg_int.json", true),
+    ("i_number_very_big_negative_int.json", true),
+    // Surrogate escapes must come in high-low pairs
+    ("i_string_1st_surrogate_but_2nd_missing.json", false),
+    ("i_string_lone_second_surrogate.json", false),
+    // Nesting deeper than the default `max_depth` of 128 is rejected
+    ("i_structure_500_nested_arrays.json", false),
+    // A leading byte order mark is skipped
+    ("i_structure_UTF-8_BOM_empty_object.json", true),
+];
+
+fn accepts(bytes: &[u8]) -> bool {
+    // The parser only works on `&str`, so invalid UTF-8 can never be accepted
+    std::str::from_utf8(bytes).is_ok_and(|input| {
+        let bump = Bump::new();
+
+        Parser::new_with_options(input, ParserOptions::strict_rfc8259())
+            .parse(&bump)
+            .is_ok()
+    })
+}
+
+#[test]
+fn json_test_suite() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_suite");
+
+    let mut misclassified = Vec::new();
+
+    for entry in fs::read_dir(dir).expect("failed to read test_suite") {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let bytes = fs::read(&path).unwrap_or_else(|_| panic!("failed to read {path:?}"));
+
+        let expected = match name.split_once('_') {
+            Some(("y", _)) => true,
+            Some(("n", _)) => false,
+            Some(("i", _)) => {
+                IMPLEMENTATION_DEFINED
+                    .iter()
+                    .find(|(case, _)| *case == name)
+                    .unwrap_or_else(|| panic!("{name} is not listed in IMPLEMENTATION_DEFINED"))
+                    .1
+            }
+            _ => panic!("{name} is not a JSONTestSuite case"),
+        };
+
+        if accepts(&bytes) != expected {
+            misclassified.push(name);
+        }
+    }
+
+    misclassified.sort();
+
+    assert!(
+        misclassified.is_empty(),
+        "misclassified: {misclassified:#?}"
+    );
+}
@@ -0,0 +1,235 @@
+use bumpalo::{Bump, collections::String};
+
+/// Why the escapes in a string couldn't be decoded by [`unescape_str_in`], with the byte
+/// offset of the offending `\` within the raw string.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A `\` followed by a character that doesn't start an escape, or by nothing at all.
+    InvalidEscape(usize),
+    /// A `\uD800`..`\uDFFF` escape that isn't part of a high-low surrogate pair.
+    LoneSurrogate(usize),
+    /// A `\u` that isn't followed by four hex digits.
+    InvalidCodepoint(usize),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::InvalidEscape(offset) => write!(f, "invalid escape at byte {offset}"),
+            DecodeError::LoneSurrogate(offset) => {
+                write!(f, "unpaired surrogate escape at byte {offset}")
+            }
+            DecodeError::InvalidCodepoint(offset) => {
+                write!(f, "invalid unicode escape at byte {offset}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Decodes the escape sequences in the raw contents of a JSON string or object key, failing
+/// on any escape that can't be decoded exactly.
+///
+/// Returns `raw` itself when it contains no escapes, and otherwise writes the decoded text
+/// into `bump`. The `\0` and `\xHH` escapes allowed by
+/// [`ParserOptions::relaxed_escapes`](crate::options::ParserOptions::relaxed_escapes) are
+/// decoded too.
+pub fn unescape_str_in<'a>(raw: &'a str, bump: &'a Bump) -> Result<&'a str, DecodeError> {
+    decode(raw, bump, false)
+}
+
+/// Decodes the escape sequences in the raw contents of a JSON string like
+/// [`unescape_str_in`], but never fails. Unpaired surrogates and malformed `\u` escapes
+/// become U+FFFD and unknown escapes the escaped character.
+pub(crate) fn unescape_in<'a>(raw: &'a str, bump: &'a Bump) -> &'a str {
+    decode(raw, bump, true).expect("lossy decoding never fails")
+}
+
+fn decode<'a>(raw: &'a str, bump: &'a Bump, lossy: bool) -> Result<&'a str, DecodeError> {
+    let Some(first_escape) = raw.find('\\') else {
+        return Ok(raw);
+    };
+
+    let mut decoded = String::with_capacity_in(raw.len(), bump);
+    decoded.push_str(&raw[..first_escape]);
+
+    let mut chars = raw[first_escape..].chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            decoded.push(ch);
+            continue;
+        }
+
+        let offset = raw.len() - chars.as_str().len() - 1;
+
+        match chars.next() {
+            Some('b') => decoded.push('\u{8}'),
+            Some('f') => decoded.push('\u{c}'),
+            Some('n') => decoded.push('\n'),
+            Some('r') => decoded.push('\r'),
+            Some('t') => decoded.push('\t'),
+            // `\"`, `\\` and `\/` decode to the escaped character itself
+            Some(escaped @ ('"' | '\\' | '/')) => decoded.push(escaped),
+            Some('0') => decoded.push('\0'),
+            Some('x') => match read_hex(&mut chars, 2) {
+                Some(code) => decoded.push(char::from(code as u8)),
+                None => {
+                    lossy_or(lossy, DecodeError::InvalidEscape(offset))?;
+                    decoded.push('\u{FFFD}');
+                }
+            },
+            Some('u') => match read_hex(&mut chars, 4) {
+                Some(high @ 0xD800..=0xDBFF) => {
+                    // A high surrogate only forms a character together with a following
+                    // `\uDC00`..`\uDFFF` escape
+                    let mut rest = chars.clone();
+
+                    match (rest.next(), rest.next(), read_hex(&mut rest, 4)) {
+                        (Some('\\'), Some('u'), Some(low @ 0xDC00..=0xDFFF)) => {
+                            let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+
+                            decoded.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                            chars = rest;
+                        }
+                        _ => {
+                            lossy_or(lossy, DecodeError::LoneSurrogate(offset))?;
+                            decoded.push('\u{FFFD}');
+                        }
+                    }
+                }
+                Some(code) => match char::from_u32(code) {
+                    Some(ch) => decoded.push(ch),
+                    // A low surrogate without a high one before it
+                    None => {
+                        lossy_or(lossy, DecodeError::LoneSurrogate(offset))?;
+                        decoded.push('\u{FFFD}');
+                    }
+                },
+                None => {
+                    lossy_or(lossy, DecodeError::InvalidCodepoint(offset))?;
+                    decoded.push('\u{FFFD}');
+                }
+            },
+            Some(escaped) if lossy => decoded.push(escaped),
+            None if lossy => decoded.push('\\'),
+            _ => return Err(DecodeError::InvalidEscape(offset)),
+        }
+    }
+
+    Ok(decoded.into_bump_str())
+}
+
+/// Fails with `error` unless decoding is lossy, in which case the caller substitutes U+FFFD.
+fn lossy_or(lossy: bool, error: DecodeError) -> Result<(), DecodeError> {
+    if lossy { Ok(()) } else { Err(error) }
+}
+
+/// Reads exactly `digits` hex digits, leaving `chars` untouched if there aren't that many.
+fn read_hex(chars: &mut std::str::Chars, digits: usize) -> Option<u32> {
+    let rest = chars.as_str();
+    let hex = rest.get(..digits)?;
+
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    *chars = rest[digits..].chars();
+
+    u32::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::JsonValue, parser::Parser};
+
+    #[test]
+    fn parsed_strings_borrow_without_escapes() {
+        let json = r#"["plain text", 1]"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+
+        let JsonValue::String(decoded) = root.as_array().unwrap()[0] else {
+            panic!("expected a string");
+        };
+
+        assert_eq!(decoded, "plain text");
+        assert_eq!(decoded.as_ptr(), json[2..].as_ptr());
+    }
+
+    #[test]
+    fn parsed_strings_decoded_into_arena() {
+        let json = r#"["tab\there \"quoted\" é 😀 \ud83d\ude00 a\/b\\"]"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+
+        let JsonValue::String(decoded) = root.as_array().unwrap()[0] else {
+            panic!("expected a string");
+        };
+
+        // SAFETY: nothing allocates in `bump` while the chunks are being inspected
+        let in_arena = unsafe { bump.iter_allocated_chunks_raw() }.any(|(start, len)| {
+            (start as *const u8..start.wrapping_add(len) as *const u8).contains(&decoded.as_ptr())
+        });
+
+        assert_eq!(decoded, "tab\there \"quoted\" é 😀 😀 a/b\\");
+        assert!(in_arena, "decoded string was not allocated in the arena");
+    }
+
+    #[test]
+    fn unescape_str_errors() {
+        let bump = Bump::new();
+
+        assert_eq!(unescape_str_in("plain", &bump), Ok("plain"));
+        assert_eq!(
+            unescape_str_in(r"\ud83d\ude00 \u00e9\n\x41", &bump),
+            Ok("😀 é\nA")
+        );
+        assert_eq!(
+            unescape_str_in(r"ab\q", &bump),
+            Err(DecodeError::InvalidEscape(2))
+        );
+        assert_eq!(
+            unescape_str_in("ab\\", &bump),
+            Err(DecodeError::InvalidEscape(2))
+        );
+        assert_eq!(
+            unescape_str_in(r"\xZZ", &bump),
+            Err(DecodeError::InvalidEscape(0))
+        );
+        assert_eq!(
+            unescape_str_in(r"é\ud800 x", &bump),
+            Err(DecodeError::LoneSurrogate(2))
+        );
+        assert_eq!(
+            unescape_str_in(r"\ud800\u0041", &bump),
+            Err(DecodeError::LoneSurrogate(0))
+        );
+        assert_eq!(
+            unescape_str_in(r"a\udc00", &bump),
+            Err(DecodeError::LoneSurrogate(1))
+        );
+        assert_eq!(
+            unescape_str_in(r"\u12G4", &bump),
+            Err(DecodeError::InvalidCodepoint(0))
+        );
+        assert_eq!(
+            unescape_str_in(r"\u+123", &bump),
+            Err(DecodeError::InvalidCodepoint(0))
+        );
+        assert_eq!(
+            DecodeError::LoneSurrogate(2).to_string(),
+            "unpaired surrogate escape at byte 2"
+        );
+
+        // The lossy decoding used while parsing substitutes instead
+        assert_eq!(
+            unescape_in(r"\ud800\u12G4\q", &bump),
+            "\u{FFFD}\u{FFFD}12G4q"
+        );
+    }
+}
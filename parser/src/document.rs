@@ -0,0 +1,176 @@
+use std::{collections::BTreeMap, mem::ManuallyDrop, ptr::NonNull};
+
+use bumpalo::Bump;
+
+use crate::{ast::JsonValue, error::ExpectedTokenError, options::ParserOptions, parser::Parser};
+
+/// A parsed document that owns both its input and the arena backing its values.
+///
+/// Parses once and can then be queried repeatedly without threading a `Bump` and the input
+/// lifetime through the caller, so it can be returned from a function that builds its input.
+#[derive(Debug)]
+pub struct Document {
+    value: ManuallyDrop<JsonValue<'static>>,
+    bump: NonNull<Bump>,
+    input: NonNull<str>,
+}
+
+/// A parser that takes ownership of its input, for callers that want to hand back a parsed
+/// value without keeping the input `String` and the `Bump` alive alongside it.
+///
+/// Parsing happens up front, so this is the same type as [`Document`]: `OwnedParser::parse`
+/// owns the input and the arena, and [`Document::value`] exposes the parsed [`JsonValue`].
+pub type OwnedParser = Document;
+
+impl Document {
+    pub fn parse(input: impl Into<String>) -> Result<Self, ExpectedTokenError> {
+        Self::parse_with_options(input, ParserOptions::default())
+    }
+
+    pub fn parse_with_options(
+        input: impl Into<String>,
+        options: ParserOptions,
+    ) -> Result<Self, ExpectedTokenError> {
+        let input = NonNull::from(Box::leak(input.into().into_boxed_str()));
+        let bump = NonNull::from(Box::leak(Box::new(Bump::new())));
+
+        // SAFETY: `input` and `bump` are heap allocations owned by the returned `Document`
+        // and only freed in `Drop` after `value`, so extending their lifetimes is sound as
+        // long as `value` is never handed out for longer than `&self`.
+        let (input_ref, bump_ref) = unsafe { (input.as_ref(), bump.as_ref()) };
+
+        match Parser::new_with_options(input_ref, options).parse(bump_ref) {
+            Ok(value) => Ok(Self {
+                value: ManuallyDrop::new(value),
+                bump,
+                input,
+            }),
+            Err(e) => {
+                // SAFETY: nothing borrows from the allocations once parsing has failed.
+                unsafe {
+                    drop(Box::from_raw(bump.as_ptr()));
+                    drop(Box::from_raw(input.as_ptr()));
+                }
+
+                Err(e)
+            }
+        }
+    }
+
+    pub fn input(&self) -> &str {
+        // SAFETY: the input is owned by `self` and never mutated.
+        unsafe { self.input.as_ref() }
+    }
+
+    pub fn value(&self) -> &JsonValue<'_> {
+        &self.value
+    }
+
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue<'_>> {
+        self.value().pointer(pointer)
+    }
+
+    pub fn select(&self, pointers: &[&str]) -> BTreeMap<String, &JsonValue<'_>> {
+        self.value().select(pointers)
+    }
+
+    pub fn flattened(&self) -> BTreeMap<String, String> {
+        self.value().flattened()
+    }
+}
+
+impl Drop for Document {
+    fn drop(&mut self) {
+        // SAFETY: `value` borrows from `bump` and `input`, so it is dropped first. Both
+        // allocations came from `Box::leak` in `Document::parse` and are freed exactly once.
+        unsafe {
+            ManuallyDrop::drop(&mut self.value);
+            drop(Box::from_raw(self.bump.as_ptr()));
+            drop(Box::from_raw(self.input.as_ptr()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load() -> Document {
+        let input =
+            String::from(r#"{"user": {"name": "John", "tags": ["admin", "ops"]}, "active": true}"#);
+
+        Document::parse(input).unwrap()
+    }
+
+    #[test]
+    fn query_document_repeatedly() {
+        let document = load();
+
+        assert_eq!(
+            document.pointer("/user/name"),
+            Some(&JsonValue::String("John"))
+        );
+        assert_eq!(
+            document.pointer("/user/tags/1"),
+            Some(&JsonValue::String("ops"))
+        );
+        assert_eq!(document.pointer("/user/missing"), None);
+        assert_eq!(
+            document.select(&["/active", "/user/tags/0"]),
+            BTreeMap::from([
+                ("/active".into(), &JsonValue::Boolean(true)),
+                ("/user/tags/0".into(), &JsonValue::String("admin")),
+            ])
+        );
+        assert_eq!(
+            document.flattened(),
+            BTreeMap::from([
+                ("active".into(), "true".into()),
+                ("user.name".into(), "John".into()),
+                ("user.tags.000".into(), "admin".into()),
+                ("user.tags.001".into(), "ops".into()),
+            ])
+        );
+    }
+
+    /// Builds its input locally and returns the parsed document, which the input couldn't
+    /// outlive when borrowed.
+    fn read_config(body: &str) -> Document {
+        let input = format!("{{\n  // generated\n  \"body\": {body},\n}}");
+        let options = ParserOptions::config_friendly();
+
+        Document::parse_with_options(input, options).unwrap()
+    }
+
+    #[test]
+    fn document_outlives_input() {
+        let document = read_config(r#"{"retries": 3}"#);
+
+        assert_eq!(
+            document.pointer("/body/retries"),
+            Some(&JsonValue::Number(3.0))
+        );
+        assert!(document.input().starts_with("{\n  // generated"));
+        assert!(Document::parse(document.input()).is_err());
+    }
+
+    #[test]
+    fn owned_parser_from_string() {
+        let input = String::from(r#"{"id": 7, "tags": ["a"]}"#);
+        let parser = OwnedParser::parse(input).unwrap();
+
+        assert_eq!(parser.pointer("/id"), Some(&JsonValue::Number(7.0)));
+        assert_eq!(
+            parser.value().pointer("/tags/0"),
+            Some(&JsonValue::String("a"))
+        );
+    }
+
+    #[test]
+    fn parse_document_error() {
+        let err = Document::parse(r#"{"a": }"#).unwrap_err();
+
+        assert_eq!(err.invalid_row, 1);
+        assert_eq!(err.invalid_col, 7);
+    }
+}
@@ -1,51 +1,368 @@
-use crate::token::TokenKind;
+use crate::token::{Span, TokenKind};
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct ExpectedTokenError {
     pub expected: Vec<TokenKind>,
     pub actual: TokenKind,
+    /// Source text of the offending token.
+    pub origin: String,
     pub invalid_row: usize,
     pub invalid_col: usize,
+    /// Byte range of the offending token, for highlighting all of it.
+    pub span: Span,
+    pub context: Option<ErrorContext>,
+}
+
+impl ExpectedTokenError {
+    /// Creates an error for `actual` appearing where one of `expected` should have been, at
+    /// row 1 column 1 with no origin, span or context until they are set.
+    pub fn new(expected: impl Into<Vec<TokenKind>>, actual: TokenKind) -> Self {
+        Self {
+            expected: expected.into(),
+            actual,
+            invalid_row: 1,
+            invalid_col: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_origin(mut self, origin: impl Into<String>) -> Self {
+        self.origin = origin.into();
+        self
+    }
+
+    pub fn at(mut self, row: usize, col: usize) -> Self {
+        self.invalid_row = row;
+        self.invalid_col = col;
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn with_context(mut self, context: ErrorContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Returns the absolute byte offset of the offending token in the input, which unlike the
+    /// column doesn't restart on every line.
+    pub fn byte_offset(&self) -> usize {
+        self.span.start
+    }
+
+    /// Returns the absolute offset of the offending token in `input` counted in `char`s, for
+    /// tooling that indexes text by character. `input` must be the text that was parsed.
+    pub fn char_offset(&self, input: &str) -> usize {
+        input[..self.span.start.min(input.len())].chars().count()
+    }
+
+    /// Returns `true` if any JSON value could have appeared where the error occurred.
+    pub fn expected_value(&self) -> bool {
+        expects_value(&self.expected)
+    }
+
+    /// Returns `true` if `delimiter` is a delimiter and was one of the expected tokens.
+    pub fn expected_delimiter(&self, delimiter: TokenKind) -> bool {
+        delimiter.is_delimiter() && self.expected.contains(&delimiter)
+    }
 }
 
 impl std::fmt::Display for ExpectedTokenError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let expected = self
-            .expected
-            .iter()
-            .map(|kind| format!("'{}'", kind.to_string().to_uppercase()))
-            .collect::<Vec<_>>()
-            .join(" | ");
-
-        // Create a local variable to store the column instead of trying to modify self
-        let mut col = self.invalid_col;
-
-        // Update the local column variable if needed based on the token kind
-        if let TokenKind::Illegal(Some(IllegalReason::String(illegal_string))) = self.actual {
-            match illegal_string {
-                IllegalString::UnescapedNewLine(column)
-                | IllegalString::UnescapedTab(column)
-                | IllegalString::InvalidUnicode(column)
-                | IllegalString::InvalidEscape(column)
-                | IllegalString::MissingClosingQuote(column) => col = column,
+        write_error(
+            f,
+            &self.expected,
+            self.actual,
+            &self.origin,
+            (self.invalid_row, self.invalid_col),
+            self.context,
+        )
+    }
+}
+
+/// Writes the message shared by [`ExpectedTokenError`] and [`BorrowedError`].
+fn write_error(
+    f: &mut std::fmt::Formatter<'_>,
+    expected: &[TokenKind],
+    actual: TokenKind,
+    origin: &str,
+    (row, mut col): (usize, usize),
+    context: Option<ErrorContext>,
+) -> std::fmt::Result {
+    // Update the column if needed based on the token kind
+    if let TokenKind::Illegal(Some(IllegalReason::String(illegal_string))) = actual {
+        match illegal_string {
+            IllegalString::UnescapedNewLine(column)
+            | IllegalString::UnescapedTab(column)
+            | IllegalString::UnescapedControlCharacter(column)
+            | IllegalString::InvalidUnicode(column)
+            | IllegalString::InvalidEscape(column)
+            | IllegalString::MissingClosingQuote(column) => col = column,
+        }
+    }
+
+    // A misspelled literal where a value belongs is best described by the word itself
+    if actual == TokenKind::Illegal(Some(IllegalReason::UnknownLiteral)) && expects_value(expected)
+    {
+        return write!(
+            f,
+            "unknown literal '{origin}' at row {row} column {col}; expected true, false, or null"
+        );
+    }
+
+    match context {
+        // Limits, precision loss and rejected keys aren't about the kind of token, so there's
+        // nothing to say it should have been, and a leading comma already names the token
+        Some(context)
+            if context.is_limit()
+                || matches!(
+                    context,
+                    ErrorContext::LeadingComma
+                        | ErrorContext::PrecisionLoss
+                        | ErrorContext::InvalidKey
+                ) =>
+        {
+            write!(f, "{context} at row {row} column {col}")
+        }
+        Some(context) => write!(
+            f,
+            "{context} at row {row} column {col} but got '{actual}' instead"
+        ),
+        None => {
+            write!(
+                f,
+                "expected token at row {row} column {col} to be one of: ("
+            )?;
+
+            for (index, kind) in expected.iter().enumerate() {
+                if index > 0 {
+                    f.write_str(" | ")?;
+                }
+
+                write!(f, "'{}'", kind.to_string().to_uppercase())?;
             }
+
+            write!(f, ") but got '{actual}' instead")
         }
+    }
+}
+
+/// Whether every kind of token that starts a value is in `expected`.
+fn expects_value(expected: &[TokenKind]) -> bool {
+    [
+        TokenKind::String,
+        TokenKind::Number,
+        TokenKind::True,
+        TokenKind::False,
+        TokenKind::Null,
+        TokenKind::LBrace,
+        TokenKind::LBracket,
+    ]
+    .iter()
+    .all(|kind| expected.contains(kind))
+}
 
-        write!(
+impl std::error::Error for ExpectedTokenError {}
+
+/// An [`ExpectedTokenError`] whose origin is a slice of the input rather than an owned copy,
+/// returned by [`parse_borrowed_err`].
+///
+/// The parser builds its errors in this form and only copies the origin when it returns an
+/// [`ExpectedTokenError`].
+///
+/// [`parse_borrowed_err`]: crate::parser::parse_borrowed_err
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BorrowedError<'a> {
+    pub expected: Vec<TokenKind>,
+    pub actual: TokenKind,
+    /// Source text of the offending token, borrowed from the input.
+    pub origin: &'a str,
+    pub invalid_row: usize,
+    pub invalid_col: usize,
+    pub span: Span,
+    pub context: Option<ErrorContext>,
+}
+
+impl<'a> BorrowedError<'a> {
+    /// Creates an error like [`ExpectedTokenError::new`].
+    pub fn new(expected: impl Into<Vec<TokenKind>>, actual: TokenKind) -> Self {
+        Self {
+            expected: expected.into(),
+            actual,
+            invalid_row: 1,
+            invalid_col: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_origin(mut self, origin: &'a str) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn at(mut self, row: usize, col: usize) -> Self {
+        self.invalid_row = row;
+        self.invalid_col = col;
+        self
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn with_context(mut self, context: ErrorContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    pub fn into_owned(self) -> ExpectedTokenError {
+        ExpectedTokenError {
+            expected: self.expected,
+            actual: self.actual,
+            origin: self.origin.to_owned(),
+            invalid_row: self.invalid_row,
+            invalid_col: self.invalid_col,
+            span: self.span,
+            context: self.context,
+        }
+    }
+}
+
+impl From<BorrowedError<'_>> for ExpectedTokenError {
+    fn from(error: BorrowedError<'_>) -> Self {
+        error.into_owned()
+    }
+}
+
+impl std::fmt::Display for BorrowedError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_error(
             f,
-            "expected token at row {} column {} to be one of: ({}) but got '{}' instead",
-            self.invalid_row, col, expected, self.actual
+            &self.expected,
+            self.actual,
+            self.origin,
+            (self.invalid_row, self.invalid_col),
+            self.context,
         )
     }
 }
 
-impl std::error::Error for ExpectedTokenError {}
+impl std::error::Error for BorrowedError<'_> {}
+
+/// Where in the document the parser was when an [`ExpectedTokenError`] occurred, used to
+/// give a more specific message than the list of expected tokens.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorContext {
+    ObjectKey,
+    ObjectColon,
+    Value,
+    /// Another member started where a `,` or `}` was expected.
+    MissingObjectComma,
+    /// Another element started where a `,` or `]` was expected.
+    MissingArrayComma,
+    /// A `,` came before the first element or member.
+    LeadingComma,
+    /// A scalar document when [`ParserOptions::require_container_root`] is set.
+    ///
+    /// [`ParserOptions::require_container_root`]: crate::options::ParserOptions::require_container_root
+    ContainerRoot,
+    /// An integer that an `f64` can't represent exactly when
+    /// [`ParserOptions::reject_precision_loss`] is set.
+    ///
+    /// [`ParserOptions::reject_precision_loss`]: crate::options::ParserOptions::reject_precision_loss
+    PrecisionLoss,
+    /// An object key that [`ParserOptions::validate_keys`] rejected.
+    ///
+    /// [`ParserOptions::validate_keys`]: crate::options::ParserOptions::validate_keys
+    InvalidKey,
+    /// More values than [`ParserOptions::max_values`] allows.
+    ///
+    /// [`ParserOptions::max_values`]: crate::options::ParserOptions::max_values
+    TooManyValues(usize),
+    /// More objects and arrays than [`ParserOptions::recursion_budget`] allows.
+    ///
+    /// [`ParserOptions::recursion_budget`]: crate::options::ParserOptions::recursion_budget
+    RecursionBudgetExhausted(usize),
+    /// Objects and arrays nested deeper than [`ParserOptions::max_depth`] allows.
+    ///
+    /// [`ParserOptions::max_depth`]: crate::options::ParserOptions::max_depth
+    TooDeeplyNested(usize),
+    /// An object with more members than [`ParserOptions::max_object_members`] allows.
+    ///
+    /// [`ParserOptions::max_object_members`]: crate::options::ParserOptions::max_object_members
+    TooManyObjectMembers(usize),
+    /// An array with more elements than [`ParserOptions::max_array_elements`] allows.
+    ///
+    /// [`ParserOptions::max_array_elements`]: crate::options::ParserOptions::max_array_elements
+    TooManyArrayElements(usize),
+}
+
+impl ErrorContext {
+    /// Returns `true` if the error comes from a configured limit rather than invalid JSON.
+    pub fn is_limit(&self) -> bool {
+        matches!(
+            self,
+            ErrorContext::TooManyValues(_)
+                | ErrorContext::RecursionBudgetExhausted(_)
+                | ErrorContext::TooDeeplyNested(_)
+                | ErrorContext::TooManyObjectMembers(_)
+                | ErrorContext::TooManyArrayElements(_)
+        )
+    }
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorContext::ObjectKey => write!(f, "expected object key (string)"),
+            ErrorContext::ObjectColon => write!(f, "expected ':' after object key"),
+            ErrorContext::Value => write!(f, "expected a JSON value"),
+            ErrorContext::MissingObjectComma => write!(f, "expected ',' between object members"),
+            ErrorContext::MissingArrayComma => write!(f, "expected ',' between array elements"),
+            ErrorContext::LeadingComma => write!(f, "unexpected ',' before the first element"),
+            ErrorContext::ContainerRoot => write!(f, "expected an object or array as the root"),
+            ErrorContext::PrecisionLoss => {
+                write!(f, "integer can't be represented exactly as an f64")
+            }
+            ErrorContext::InvalidKey => write!(f, "object key rejected by validate_keys"),
+            ErrorContext::TooManyValues(max) => {
+                write!(f, "document has more than the maximum of {max} values")
+            }
+            ErrorContext::RecursionBudgetExhausted(budget) => {
+                write!(
+                    f,
+                    "document exhausted the recursion budget of {budget} containers"
+                )
+            }
+            ErrorContext::TooDeeplyNested(max) => {
+                write!(f, "nesting is deeper than the maximum of {max} levels")
+            }
+            ErrorContext::TooManyObjectMembers(max) => {
+                write!(f, "object has more than the maximum of {max} members")
+            }
+            ErrorContext::TooManyArrayElements(max) => {
+                write!(f, "array has more than the maximum of {max} elements")
+            }
+        }
+    }
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum IllegalReason {
     Character(char),
     Number(IllegalNumber),
     String(IllegalString),
+    UnterminatedComment,
+    /// A word other than `true`, `false` or `null`, such as `undefined` or `None`.
+    UnknownLiteral,
+    /// The literal is longer than [`ParserOptions::max_token_len`] bytes.
+    ///
+    /// [`ParserOptions::max_token_len`]: crate::options::ParserOptions::max_token_len
+    TooLong(usize),
 }
 
 impl std::fmt::Display for IllegalReason {
@@ -54,6 +371,9 @@ impl std::fmt::Display for IllegalReason {
             IllegalReason::Character(c) => &format!("invalid character: '{c}'"),
             IllegalReason::Number(e) => &format!("invalid number: {e}"),
             IllegalReason::String(e) => &format!("invalid string: {e}"),
+            IllegalReason::UnterminatedComment => "unterminated block comment",
+            IllegalReason::UnknownLiteral => "unknown literal",
+            IllegalReason::TooLong(max) => &format!("longer than the maximum of {max} bytes"),
         };
 
         write!(f, "{value}")
@@ -89,6 +409,7 @@ impl std::fmt::Display for IllegalNumber {
 pub enum IllegalString {
     UnescapedNewLine(usize),
     UnescapedTab(usize),
+    UnescapedControlCharacter(usize),
     InvalidUnicode(usize),
     InvalidEscape(usize),
     MissingClosingQuote(usize),
@@ -99,6 +420,7 @@ impl std::fmt::Display for IllegalString {
         let value = match self {
             IllegalString::UnescapedNewLine(_) => "unescaped newline",
             IllegalString::UnescapedTab(_) => "unescaped tab",
+            IllegalString::UnescapedControlCharacter(_) => "unescaped control character",
             IllegalString::InvalidUnicode(_) => "invalid unicode",
             IllegalString::InvalidEscape(_) => "invalid escape",
             IllegalString::MissingClosingQuote(_) => "missing closing quote",
@@ -108,6 +430,48 @@ impl std::fmt::Display for IllegalString {
     }
 }
 
+/// Returns early with a [`BorrowedError`] for `$actual_token`, a [`Token`], given the
+/// position and the expected [`TokenKind`] variants, optionally followed by `; Context` naming
+/// an [`ErrorContext`] variant.
+///
+/// [`Token`]: crate::token::Token
+#[macro_export]
+macro_rules! expected_token_err {
+    ($actual_token:expr, $row:expr, $column:expr, $( $variant:ident )|+; $context:ident) => {
+        return Err($crate::error::BorrowedError {
+            expected: vec![$($crate::token::TokenKind::$variant),+],
+            actual: $actual_token.kind,
+            origin: $actual_token.origin,
+            invalid_row: $row,
+            invalid_col: $column,
+            span: $actual_token.span,
+            context: Some($crate::error::ErrorContext::$context),
+        })
+    };
+    ($actual_token:expr, $row:expr, $column:expr, $expected_token:path) => {
+        return Err($crate::error::BorrowedError {
+            expected: vec![$expected_token],
+            actual: $actual_token.kind,
+            origin: $actual_token.origin,
+            invalid_row: $row,
+            invalid_col: $column,
+            span: $actual_token.span,
+            context: None,
+        })
+    };
+    ($actual_token:expr, $row:expr, $column:expr, $( $variant:ident )|+) => {
+        return Err($crate::error::BorrowedError {
+            expected: vec![$($crate::token::TokenKind::$variant),+],
+            actual: $actual_token.kind,
+            origin: $actual_token.origin,
+            invalid_row: $row,
+            invalid_col: $column,
+            span: $actual_token.span,
+            context: None,
+        })
+    };
+}
+
 #[macro_export]
 macro_rules! illegal_number {
     ($variant:ident) => {
@@ -125,3 +489,79 @@ macro_rules! illegal_string {
         ))
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn parse_err(input: &str) -> ExpectedTokenError {
+        let bump = Bump::new();
+
+        Parser::new(input).parse(&bump).unwrap_err()
+    }
+
+    #[test]
+    fn expected_predicates() {
+        let missing_value = parse_err(r#"{"a": }"#);
+
+        assert!(missing_value.expected_value());
+        assert!(!missing_value.expected_delimiter(TokenKind::Comma));
+
+        let missing_comma = parse_err("[1 2]");
+
+        assert!(!missing_comma.expected_value());
+        assert!(missing_comma.expected_delimiter(TokenKind::Comma));
+        assert!(missing_comma.expected_delimiter(TokenKind::RBracket));
+        assert!(!missing_comma.expected_delimiter(TokenKind::RBrace));
+
+        let missing_key = parse_err("{1: 2}");
+
+        assert!(!missing_key.expected_value());
+        assert!(!missing_key.expected_delimiter(TokenKind::String));
+    }
+
+    #[test]
+    fn error_span_covers_token() {
+        let input = r#"{"total": -0012.50e}"#;
+        let err = parse_err(input);
+
+        assert_eq!(err.actual, illegal_number!(LeadingZero));
+        assert_eq!(err.span, Span { start: 10, end: 19 });
+        assert_eq!(&input[err.span.start..err.span.end], "-0012.50e");
+    }
+
+    #[test]
+    fn build_error() {
+        let err = ExpectedTokenError::new([TokenKind::Colon], TokenKind::Number)
+            .with_origin("42")
+            .at(3, 9)
+            .with_span(Span { start: 20, end: 22 });
+
+        assert_eq!(err.origin, "42");
+        assert_eq!(err.context, None);
+        assert_eq!(
+            err.to_string(),
+            "expected token at row 3 column 9 to be one of: (':') but got 'NUMBER' instead"
+        );
+        assert_eq!(
+            err.with_context(ErrorContext::ObjectColon).to_string(),
+            "expected ':' after object key at row 3 column 9 but got 'NUMBER' instead"
+        );
+        assert_eq!(parse_err("[1, 02]").origin, "02");
+    }
+
+    #[test]
+    fn absolute_offsets() {
+        let input = "{\n  \"naïve\": [1, 2],\n  \"é\": tru\n}";
+        let err = parse_err(input);
+
+        assert_eq!((err.invalid_row, err.invalid_col), (3, 8));
+        assert_eq!(err.byte_offset(), input.find("tru").unwrap());
+        assert_eq!(err.byte_offset(), 30);
+        assert_eq!(err.char_offset(input), 28);
+    }
+}
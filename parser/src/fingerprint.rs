@@ -0,0 +1,152 @@
+use crate::ast::JsonValue;
+
+/// 64-bit FNV-1a, which unlike the std hashers is guaranteed to give the same result in every
+/// run and release.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new(tag: u8) -> Self {
+        let mut hasher = Self(0xcbf2_9ce4_8422_2325);
+
+        hasher.write(&[tag]);
+        hasher
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// Writes a length as 8 bytes whatever the platform's pointer width.
+    fn write_len(&mut self, len: usize) {
+        self.write(&(len as u64).to_le_bytes());
+    }
+
+    fn write_str(&mut self, val: &str) {
+        self.write_len(val.len());
+        self.write(val.as_bytes());
+    }
+}
+
+impl JsonValue<'_> {
+    /// Returns a hash of the value that is stable across runs, for caching and change
+    /// detection.
+    ///
+    /// Values that mean the same thing hash the same: object members may be in any order,
+    /// strings and keys are compared as decoded text, and numbers by value, so `1.0`
+    /// and `1e0` match, as do `0` and `-0`.
+    pub fn fingerprint(&self) -> u64 {
+        let hasher = match self {
+            JsonValue::Null => Fnv1a::new(0),
+            JsonValue::Boolean(val) => {
+                let mut hasher = Fnv1a::new(1);
+
+                hasher.write(&[u8::from(*val)]);
+                hasher
+            }
+            JsonValue::Number(val) => {
+                let mut hasher = Fnv1a::new(2);
+                // `-0.0 == 0.0`, so both map to the bits of `0.0`
+                let val = if *val == 0.0 { 0.0 } else { *val };
+
+                hasher.write(&val.to_bits().to_le_bytes());
+                hasher
+            }
+            JsonValue::String(val) => {
+                let mut hasher = Fnv1a::new(3);
+
+                hasher.write_str(val);
+                hasher
+            }
+            JsonValue::Array(json_values) => {
+                let mut hasher = Fnv1a::new(4);
+
+                hasher.write_len(json_values.len());
+
+                for value in json_values {
+                    hasher.write(&value.fingerprint().to_le_bytes());
+                }
+
+                hasher
+            }
+            JsonValue::Object(properties) => {
+                let mut members = properties
+                    .iter()
+                    .map(|property| {
+                        let mut member = Fnv1a::new(6);
+
+                        member.write_str(property.key_str());
+                        member.write(&property.value.fingerprint().to_le_bytes());
+                        member.0
+                    })
+                    .collect::<Vec<_>>();
+
+                // Sorting the member hashes makes the result independent of their order
+                members.sort_unstable();
+
+                let mut hasher = Fnv1a::new(5);
+
+                hasher.write_len(members.len());
+
+                for member in members {
+                    hasher.write(&member.to_le_bytes());
+                }
+
+                hasher
+            }
+        };
+
+        hasher.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use crate::parser::Parser;
+
+    fn fingerprint(json: &str) -> u64 {
+        let bump = Bump::new();
+
+        Parser::new(json).parse(&bump).unwrap().fingerprint()
+    }
+
+    #[test]
+    fn equal_documents_match() {
+        let original = fingerprint(r#"{"a": 1, "b": [true, null, "x"], "c": {"d": 0.5, "e": -0}}"#);
+
+        assert_eq!(
+            original,
+            fingerprint(
+                r#"{ "c": {"e": 0, "d": 5e-1}, "b": [true, null, "x"],
+                    "a": 1.0 }"#
+            )
+        );
+
+        for different in [
+            r#"{"a": 1, "b": [null, true, "x"], "c": {"d": 0.5, "e": 0}}"#,
+            r#"{"a": 1, "b": [true, null, "x"], "c": {"d": 0.5, "f": 0}}"#,
+            r#"{"a": 1, "b": [true, null, "x"], "c": {"d": 0.5, "e": "0"}}"#,
+            r#"{"a": 1, "b": [true, null, "x"], "c": {"d": 0.5, "e": 0}, "g": null}"#,
+            r#"[{"a": 1, "b": [true, null, "x"], "c": {"d": 0.5, "e": 0}}]"#,
+        ] {
+            assert_ne!(original, fingerprint(different), "{different}");
+        }
+
+        assert_eq!(fingerprint(r#"["x"]"#), fingerprint(r#"["\u0078"]"#));
+        assert_ne!(fingerprint(r#"["ab", "c"]"#), fingerprint(r#"["a", "bc"]"#));
+        assert_ne!(fingerprint("[[], []]"), fingerprint("[[[]]]"));
+        assert_ne!(fingerprint("{}"), fingerprint("[]"));
+    }
+
+    #[test]
+    fn stable_across_runs() {
+        // Pinned so that any change to the hashing, which would invalidate stored
+        // fingerprints, is noticed
+        assert_eq!(fingerprint("null"), 0xaf63_bd4c_8601_b7df);
+        assert_eq!(fingerprint(r#"{"a": [1, "b"]}"#), 0xf3f1_8263_d3d1_c7f7);
+    }
+}
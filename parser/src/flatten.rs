@@ -0,0 +1,905 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt::Write};
+
+use bumpalo::Bump;
+
+use crate::{
+    ast::{JsonProperty, JsonValue, format_number},
+    error::ExpectedTokenError,
+    parser::Parser,
+};
+
+/// How [`JsonValue::Null`] leaves are written by [`JsonValue::flattened_with`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum NullRendering {
+    /// The string `"null"`.
+    #[default]
+    Literal,
+    /// An empty string.
+    Empty,
+    /// Leave the entry out entirely.
+    Omit,
+}
+
+/// Parses `input` and flattens it with the default [`FlattenOptions`], returning an owned map
+/// so the arena used while parsing never escapes.
+pub fn parse_flattened(input: &str) -> Result<BTreeMap<String, String>, ExpectedTokenError> {
+    let bump = Bump::new();
+
+    Ok(Parser::new(input).parse(&bump)?.flattened())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlattenOptions {
+    /// Number of digits array indices are zero-padded to.
+    pub index_width: usize,
+    pub null_as: NullRendering,
+    /// Separator placed between the segments of a flattened key.
+    pub delimiter: char,
+    /// Percent-encode `%` and the delimiter within object keys, so that keys containing the
+    /// delimiter can't be confused with nesting and [`JsonValue::unflatten`] restores them.
+    pub escape_keys: bool,
+    /// Write empty objects and arrays as the values `{}` and `[]` instead of leaving them out,
+    /// which [`JsonValue::unflatten`] turns back into empty containers. Strings with those
+    /// exact contents then can't be told apart from them.
+    pub mark_empty_containers: bool,
+}
+
+impl Default for FlattenOptions {
+    fn default() -> Self {
+        Self {
+            index_width: 3,
+            null_as: NullRendering::default(),
+            delimiter: '.',
+            escape_keys: false,
+            mark_empty_containers: false,
+        }
+    }
+}
+
+impl JsonValue<'_> {
+    pub fn flattened(&self) -> BTreeMap<String, String> {
+        self.flattened_with(&FlattenOptions::default())
+    }
+
+    /// Flattens the value, zero-padding array indices to `width` digits.
+    ///
+    /// Flattening several documents with the same width, e.g. the largest
+    /// [`JsonValue::array_index_width`] among them, keeps their keys comparable when the
+    /// resulting maps are merged.
+    pub fn flattened_with_width(&self, width: usize) -> BTreeMap<String, String> {
+        self.flattened_with(&FlattenOptions {
+            index_width: width,
+            ..Default::default()
+        })
+    }
+
+    /// Flattens the value into a map from the key of every leaf to its text.
+    ///
+    /// Numbers are written in their shortest round-tripping form, and non-finite ones as
+    /// `NaN`, `Infinity` or `-Infinity`.
+    pub fn flattened_with(&self, options: &FlattenOptions) -> BTreeMap<String, String> {
+        let mut res = BTreeMap::new();
+
+        self.flatten(options, &mut |key, value| {
+            res.insert(key, value);
+        });
+
+        res
+    }
+
+    /// Flattens the value like [`JsonValue::flattened`] and serializes the result as a JSON
+    /// object with a string value for every leaf.
+    pub fn flattened_json(&self) -> String {
+        let bump = Bump::new();
+        let flattened = self.flattened();
+
+        let properties = bumpalo::collections::Vec::from_iter_in(
+            flattened
+                .iter()
+                .map(|(key, value)| JsonProperty::from((key.as_str(), JsonValue::String(value)))),
+            &bump,
+        );
+
+        JsonValue::Object(properties)
+            .to_json_string()
+            .expect("an object of strings has no numbers to reject")
+    }
+
+    /// Flattens the value like [`JsonValue::flattened`] but keeps document order, walking
+    /// object properties in order and arrays by index.
+    ///
+    /// Repeated object keys produce one entry each rather than the last one winning.
+    pub fn flattened_ordered(&self) -> Vec<(String, String)> {
+        let mut res = Vec::new();
+
+        self.flatten(&FlattenOptions::default(), &mut |key, value| {
+            res.push((key, value));
+        });
+
+        res
+    }
+
+    /// Returns the number of digits needed to write the largest array index in the document.
+    pub fn array_index_width(&self) -> usize {
+        match self {
+            JsonValue::Object(properties) => properties
+                .iter()
+                .map(|property| property.value.array_index_width())
+                .max()
+                .unwrap_or(1),
+            JsonValue::Array(json_values) => {
+                let last_index = json_values.len().saturating_sub(1);
+                let width = last_index.checked_ilog10().unwrap_or(0) as usize + 1;
+
+                json_values
+                    .iter()
+                    .map(|value| value.array_index_width())
+                    .fold(width, usize::max)
+            }
+            _ => 1,
+        }
+    }
+
+    /// Walks the value in document order, calling `emit` with the key and text of every
+    /// leaf.
+    ///
+    /// Uses an explicit stack rather than recursion, so that arbitrarily deep trees can't
+    /// overflow the call stack, and builds keys in a single buffer.
+    fn flatten(&self, options: &FlattenOptions, emit: &mut impl FnMut(String, String)) {
+        let mut key = String::new();
+        let mut stack: std::vec::Vec<(Children<'_, '_>, usize)> = std::vec::Vec::new();
+        let mut next = Some(self);
+
+        loop {
+            match next.take() {
+                Some(JsonValue::Object(properties))
+                    if properties.is_empty() && options.mark_empty_containers =>
+                {
+                    emit(key.clone(), "{}".to_string());
+                }
+                Some(JsonValue::Array(json_values))
+                    if json_values.is_empty() && options.mark_empty_containers =>
+                {
+                    emit(key.clone(), "[]".to_string());
+                }
+                Some(JsonValue::Object(properties)) => {
+                    stack.push((Children::Object(properties.iter()), key.len()));
+                }
+                Some(JsonValue::Array(json_values)) => {
+                    stack.push((Children::Array(json_values.iter().enumerate()), key.len()));
+                }
+                Some(JsonValue::Null) => match options.null_as {
+                    NullRendering::Literal => emit(key.clone(), "null".to_string()),
+                    NullRendering::Empty => emit(key.clone(), String::new()),
+                    NullRendering::Omit => (),
+                },
+                Some(JsonValue::Boolean(val)) => emit(key.clone(), val.to_string()),
+                Some(JsonValue::Number(val)) => emit(key.clone(), format_number(*val)),
+                Some(JsonValue::String(val)) => emit(key.clone(), val.to_string()),
+                None => (),
+            }
+
+            let Some((children, parent_len)) = stack.last_mut() else {
+                break;
+            };
+
+            key.truncate(*parent_len);
+
+            let separator = if key.is_empty() {
+                None
+            } else {
+                Some(options.delimiter)
+            };
+
+            match children {
+                Children::Object(properties) => match properties.next() {
+                    Some(property) => {
+                        key.extend(separator);
+
+                        if options.escape_keys {
+                            key.push_str(&escape_key(property.key_str(), options.delimiter));
+                        } else {
+                            key.push_str(property.key_str());
+                        }
+
+                        next = Some(&property.value);
+                    }
+                    None => {
+                        stack.pop();
+                    }
+                },
+                Children::Array(json_values) => match json_values.next() {
+                    Some((index, value)) => {
+                        key.extend(separator);
+
+                        write!(key, "{:0width$}", index, width = options.index_width)
+                            .expect("writing to a String never fails");
+
+                        next = Some(value);
+                    }
+                    None => {
+                        stack.pop();
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// The remaining children of an object or array being flattened.
+enum Children<'v, 'a> {
+    Object(std::slice::Iter<'v, JsonProperty<'a>>),
+    Array(std::iter::Enumerate<std::slice::Iter<'v, JsonValue<'a>>>),
+}
+
+impl<'a> JsonValue<'a> {
+    /// Rebuilds a value from a map produced by [`JsonValue::flattened_with`] using the same
+    /// `options`.
+    ///
+    /// Every leaf comes back as a string, and a level whose keys are exactly the padded
+    /// indices `0..n` becomes an array. Empty objects and arrays are only restored when
+    /// flattened with [`FlattenOptions::mark_empty_containers`]. Flattening the result gives
+    /// back `flat`.
+    pub fn unflatten(
+        flat: &'a BTreeMap<String, String>,
+        options: &FlattenOptions,
+        bump: &'a Bump,
+    ) -> JsonValue<'a> {
+        let mut root = Node::Branch(std::vec::Vec::new());
+
+        for (key, value) in flat {
+            if key.is_empty() {
+                // A scalar document flattens to a single entry with an empty key
+                root = Node::Leaf(value);
+                continue;
+            }
+
+            let mut node = &mut root;
+
+            for segment in key.split(options.delimiter) {
+                let segment = if options.escape_keys {
+                    unescape_key(segment, bump)
+                } else {
+                    segment
+                };
+
+                if let Node::Leaf(_) = node {
+                    *node = Node::Branch(std::vec::Vec::new());
+                }
+
+                let Node::Branch(children) = node else {
+                    unreachable!()
+                };
+
+                let position = match children.iter().position(|(name, _)| *name == segment) {
+                    Some(position) => position,
+                    None => {
+                        children.push((segment, Node::Branch(std::vec::Vec::new())));
+                        children.len() - 1
+                    }
+                };
+
+                node = &mut children[position].1;
+            }
+
+            *node = Node::Leaf(value);
+        }
+
+        root.into_value(options, bump)
+    }
+
+    /// Replaces every string of the form `"${key}"` with the string `values[key]`, such as a
+    /// flat map of settings filled into a skeleton config.
+    ///
+    /// Substituted values are always strings. Strings that aren't placeholders, and
+    /// placeholders whose key is missing from `values`, are kept unchanged.
+    pub fn fill_template(
+        &self,
+        values: &'a BTreeMap<String, String>,
+        bump: &'a Bump,
+    ) -> JsonValue<'a> {
+        match self {
+            JsonValue::String(val) => {
+                let value = val
+                    .strip_prefix("${")
+                    .and_then(|placeholder| placeholder.strip_suffix('}'))
+                    .and_then(|key| values.get(key));
+
+                match value {
+                    Some(value) => JsonValue::String(value),
+                    None => self.clone(),
+                }
+            }
+            JsonValue::Object(properties) => {
+                let mut items = bumpalo::collections::Vec::with_capacity_in(properties.len(), bump);
+
+                items.extend(properties.iter().map(|property| {
+                    property.with_value(property.value.fill_template(values, bump))
+                }));
+
+                JsonValue::Object(items)
+            }
+            JsonValue::Array(json_values) => {
+                let mut items =
+                    bumpalo::collections::Vec::with_capacity_in(json_values.len(), bump);
+
+                items.extend(
+                    json_values
+                        .iter()
+                        .map(|value| value.fill_template(values, bump)),
+                );
+
+                JsonValue::Array(items)
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+enum Node<'a> {
+    Leaf(&'a str),
+    Branch(std::vec::Vec<(&'a str, Node<'a>)>),
+}
+
+impl<'a> Node<'a> {
+    fn into_value(self, options: &FlattenOptions, bump: &'a Bump) -> JsonValue<'a> {
+        let children = match self {
+            Node::Leaf("{}") if options.mark_empty_containers => {
+                return JsonValue::Object(bumpalo::collections::Vec::new_in(bump));
+            }
+            Node::Leaf("[]") if options.mark_empty_containers => {
+                return JsonValue::Array(bumpalo::collections::Vec::new_in(bump));
+            }
+            Node::Leaf(value) => return JsonValue::String(value),
+            Node::Branch(children) => children,
+        };
+
+        let is_array = !children.is_empty()
+            && children.iter().enumerate().all(|(index, (segment, _))| {
+                *segment == format!("{:0width$}", index, width = options.index_width)
+            });
+
+        if is_array {
+            let mut items = bumpalo::collections::Vec::with_capacity_in(children.len(), bump);
+
+            items.extend(
+                children
+                    .into_iter()
+                    .map(|(_, child)| child.into_value(options, bump)),
+            );
+
+            JsonValue::Array(items)
+        } else {
+            let mut items = bumpalo::collections::Vec::with_capacity_in(children.len(), bump);
+
+            items.extend(
+                children
+                    .into_iter()
+                    .map(|(key, child)| JsonProperty::from((key, child.into_value(options, bump)))),
+            );
+
+            JsonValue::Object(items)
+        }
+    }
+}
+
+fn escape_key(key: &str, delimiter: char) -> Cow<'_, str> {
+    if !key.contains(['%', delimiter]) {
+        return Cow::Borrowed(key);
+    }
+
+    let mut escaped = String::with_capacity(key.len() + 2);
+
+    for ch in key.chars() {
+        if ch == '%' || ch == delimiter {
+            for byte in ch.encode_utf8(&mut [0; 4]).bytes() {
+                escaped.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            escaped.push(ch);
+        }
+    }
+
+    Cow::Owned(escaped)
+}
+
+fn unescape_key<'a>(segment: &'a str, bump: &'a Bump) -> &'a str {
+    if !segment.contains('%') {
+        return segment;
+    }
+
+    let mut bytes = std::vec::Vec::with_capacity(segment.len());
+    let mut rest = segment.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (byte, decoded) {
+            (b'%', Some(decoded)) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    bump.alloc_str(&String::from_utf8_lossy(&bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::{Bump, vec};
+
+    use super::*;
+    use crate::ast::JsonProperty;
+
+    #[test]
+    fn flattened_json_round_trips() {
+        let json =
+            r#"{"user": {"name": "Jo\"hn", "tags": ["a", "b"]}, "n": -1.5, "ok": true, "x": null}"#;
+
+        let bump = Bump::new();
+        let value = Parser::new(json).parse(&bump).unwrap();
+        let output = value.flattened_json();
+        let reparsed = Parser::new(&output).parse(&bump).unwrap();
+
+        assert_eq!(
+            output,
+            r#"{"n":"-1.5","ok":"true","user.name":"Jo\"hn","user.tags.000":"a","user.tags.001":"b","x":"null"}"#
+        );
+        assert_eq!(reparsed.flattened(), value.flattened());
+        assert_eq!(JsonValue::Null.flattened_json(), r#"{"":"null"}"#);
+    }
+
+    #[test]
+    fn root_object_flattened() {
+        let bump = Bump::new();
+
+        let root = JsonValue::Object(vec![
+            in &bump;
+            JsonProperty::from(("name", JsonValue::String("John"))),
+            JsonProperty::from(("age", JsonValue::Number(30.0))),
+            JsonProperty::from(("isStudent", JsonValue::Boolean(false))),
+            JsonProperty::from((
+                "address",
+                JsonValue::Object(vec![
+                    in &bump;
+                    JsonProperty::from((
+                        "street",
+                        JsonValue::Object(vec![
+                            in &bump;
+                            JsonProperty::from((
+                                "number",
+                                JsonValue::Number(95.0),
+                            ))]),
+                    )),
+                    JsonProperty::from(("city", JsonValue::String("New York"))),
+                    JsonProperty::from(("zipcode", JsonValue::Null)),
+                ]),
+            )),
+            JsonProperty::from((
+                "courses",
+                JsonValue::Array(vec![
+                    in &bump;
+                    JsonValue::Object(vec![
+                        in &bump;
+                        JsonProperty::from(("courseName", JsonValue::String("Math"))),
+                        JsonProperty::from(("grade", JsonValue::String("A"))),
+                    ]),
+                    JsonValue::Object(vec![
+                        in &bump;
+                        JsonProperty::from(("courseName", JsonValue::String("Science"))),
+                        JsonProperty::from(("grade", JsonValue::String("B"))),
+                    ]),
+                ]),
+            )),
+            JsonProperty::from((
+                "preferences",
+                JsonValue::Object(vec![
+                    in &bump;
+                    JsonProperty::from(("notifications", JsonValue::Boolean(true))),
+                    JsonProperty::from(("theme", JsonValue::String("dark"))),
+                ]),
+            )),
+            JsonProperty::from((
+                "scores",
+                JsonValue::Array(vec![
+                    in &bump;
+                    JsonValue::Number(95.0),
+                    JsonValue::Number(88.0),
+                    JsonValue::Number(76.0),
+                ]),
+            )),
+            JsonProperty::from((
+                "metadata",
+                JsonValue::Object(vec![
+                    in &bump;
+                    JsonProperty::from(("createdAt", JsonValue::String("2023-10-01T12:34:56Z"))),
+                    JsonProperty::from(("updatedAt", JsonValue::String("2023-10-01T12:34:56Z"))),
+                ]),
+            )),
+        ]);
+
+        assert_eq!(
+            root.flattened(),
+            BTreeMap::from([
+                ("address.city".into(), "New York".into()),
+                ("address.street.number".into(), "95".into()),
+                ("address.zipcode".into(), "null".into()),
+                ("age".into(), "30".into()),
+                ("courses.000.courseName".into(), "Math".into()),
+                ("courses.000.grade".into(), "A".into()),
+                ("courses.001.courseName".into(), "Science".into()),
+                ("courses.001.grade".into(), "B".into()),
+                ("isStudent".into(), "false".into()),
+                ("metadata.createdAt".into(), "2023-10-01T12:34:56Z".into()),
+                ("metadata.updatedAt".into(), "2023-10-01T12:34:56Z".into()),
+                ("name".into(), "John".into()),
+                ("preferences.notifications".into(), "true".into()),
+                ("preferences.theme".into(), "dark".into()),
+                ("scores.000".into(), "95".into()),
+                ("scores.001".into(), "88".into()),
+                ("scores.002".into(), "76".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn root_array_flattened() {
+        let bump = Bump::new();
+
+        let root = JsonValue::Array(vec![
+            in &bump;
+            JsonValue::Object(vec![
+                in &bump;
+                JsonProperty::from(("albumId", JsonValue::Number(1.0))),
+                JsonProperty::from(("id", JsonValue::Number(1.0))),
+                JsonProperty::from((
+                    "title",
+                    JsonValue::String("accusamus beatae ad facilis cum similique qui sunt"),
+                )),
+                JsonProperty::from((
+                    "url",
+                    JsonValue::String("https://via.placeholder.com/600/92c952"),
+                )),
+                JsonProperty::from((
+                    "thumbnailUrl",
+                    JsonValue::String("https://via.placeholder.com/150/92c952"),
+                )),
+            ]),
+            JsonValue::Object(vec![
+                in &bump;
+                JsonProperty::from(("albumId", JsonValue::Number(1.0))),
+                JsonProperty::from(("id", JsonValue::Number(2.0))),
+                JsonProperty::from((
+                    "title",
+                    JsonValue::String("reprehenderit est deserunt velit ipsam"),
+                )),
+                JsonProperty::from((
+                    "url",
+                    JsonValue::String("https://via.placeholder.com/600/771796"),
+                )),
+                JsonProperty::from((
+                    "thumbnailUrl",
+                    JsonValue::String("https://via.placeholder.com/150/771796"),
+                )),
+            ]),
+        ]);
+
+        assert_eq!(
+            root.flattened(),
+            BTreeMap::from([
+                ("000.albumId".into(), "1".into()),
+                ("000.id".into(), "1".into()),
+                (
+                    "000.thumbnailUrl".into(),
+                    "https://via.placeholder.com/150/92c952".into()
+                ),
+                (
+                    "000.title".into(),
+                    "accusamus beatae ad facilis cum similique qui sunt".into()
+                ),
+                (
+                    "000.url".into(),
+                    "https://via.placeholder.com/600/92c952".into()
+                ),
+                ("001.albumId".into(), "1".into()),
+                ("001.id".into(), "2".into()),
+                (
+                    "001.thumbnailUrl".into(),
+                    "https://via.placeholder.com/150/771796".into()
+                ),
+                (
+                    "001.title".into(),
+                    "reprehenderit est deserunt velit ipsam".into()
+                ),
+                (
+                    "001.url".into(),
+                    "https://via.placeholder.com/600/771796".into()
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn flattened_with_shared_width() {
+        let bump = Bump::new();
+
+        let large = format!(
+            r#"{{"items": [{}]}}"#,
+            (0..1001)
+                .map(|i| i.to_string())
+                .collect::<std::vec::Vec<_>>()
+                .join(",")
+        );
+        let large = Parser::new(&large).parse(&bump).unwrap();
+        let small = Parser::new(r#"{"items": ["a", "b", "c"]}"#)
+            .parse(&bump)
+            .unwrap();
+
+        assert_eq!(large.array_index_width(), 4);
+        assert_eq!(small.array_index_width(), 1);
+
+        let width = large.array_index_width().max(small.array_index_width());
+
+        let large = large.flattened_with_width(width);
+        let small = small.flattened_with_width(width);
+
+        assert_eq!(
+            small,
+            BTreeMap::from([
+                ("items.0000".into(), "a".into()),
+                ("items.0001".into(), "b".into()),
+                ("items.0002".into(), "c".into()),
+            ])
+        );
+
+        let keys = large.keys().collect::<std::vec::Vec<_>>();
+
+        assert_eq!(keys.len(), 1001);
+        assert_eq!(keys[999], "items.0999");
+        assert_eq!(keys[1000], "items.1000");
+        assert!(small.keys().all(|key| large.contains_key(key)));
+    }
+
+    #[test]
+    fn flattened_null_rendering() {
+        let bump = Bump::new();
+        let root = Parser::new(r#"{"name": "John", "zipcode": null, "tags": [null, "a"]}"#)
+            .parse(&bump)
+            .unwrap();
+
+        let flattened = |null_as| {
+            root.flattened_with(&FlattenOptions {
+                null_as,
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(flattened(NullRendering::Literal), root.flattened());
+        assert_eq!(
+            flattened(NullRendering::Literal),
+            BTreeMap::from([
+                ("name".into(), "John".into()),
+                ("tags.000".into(), "null".into()),
+                ("tags.001".into(), "a".into()),
+                ("zipcode".into(), "null".into()),
+            ])
+        );
+        assert_eq!(
+            flattened(NullRendering::Empty),
+            BTreeMap::from([
+                ("name".into(), "John".into()),
+                ("tags.000".into(), "".into()),
+                ("tags.001".into(), "a".into()),
+                ("zipcode".into(), "".into()),
+            ])
+        );
+        assert_eq!(
+            flattened(NullRendering::Omit),
+            BTreeMap::from([
+                ("name".into(), "John".into()),
+                ("tags.001".into(), "a".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn flattened_in_document_order() {
+        let json = r#"{"zeta": 1, "alpha": {"y": [true, null], "b": "two"}, "mid": []}"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+
+        let ordered = root.flattened_ordered();
+
+        assert_eq!(
+            ordered,
+            [
+                ("zeta", "1"),
+                ("alpha.y.000", "true"),
+                ("alpha.y.001", "null"),
+                ("alpha.b", "two"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+        assert_eq!(
+            ordered.into_iter().collect::<BTreeMap<_, _>>(),
+            root.flattened()
+        );
+    }
+
+    #[test]
+    fn unflatten_keys_containing_delimiter() {
+        let json =
+            r#"{"a.b": {"c": 1, "50%": [true, null]}, "a": {"b.c": "x", "/": {}}, "list": ["y"]}"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+
+        let options = FlattenOptions {
+            escape_keys: true,
+            ..Default::default()
+        };
+        let flat = root.flattened_with(&options);
+
+        assert_eq!(
+            flat.keys().collect::<std::vec::Vec<_>>(),
+            [
+                "a%2Eb.50%25.000",
+                "a%2Eb.50%25.001",
+                "a%2Eb.c",
+                "a.b%2Ec",
+                "list.000"
+            ]
+        );
+
+        let restored = JsonValue::unflatten(&flat, &options, &bump);
+
+        assert_eq!(restored.flattened_with(&options), flat);
+        assert_eq!(
+            restored,
+            Parser::new(
+                r#"{"a.b": {"50%": ["true", "null"], "c": "1"}, "a": {"b.c": "x"}, "list": ["y"]}"#
+            )
+            .parse(&bump)
+            .unwrap()
+        );
+
+        // Without escaping the two keys collide
+        assert_eq!(root.flattened().get("a.b.c"), Some(&"x".to_string()));
+
+        let slash = FlattenOptions {
+            delimiter: '/',
+            ..Default::default()
+        };
+
+        assert_eq!(
+            root.flattened_with(&slash).get("a.b/50%/001"),
+            Some(&"null".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_flattened_matches_two_steps() {
+        let input = include_str!("../../test_data/apache_builds.json");
+
+        let bump = Bump::new();
+        let flat = Parser::new(input).parse(&bump).unwrap().flattened();
+
+        assert_eq!(parse_flattened(input), Ok(flat));
+        assert!(parse_flattened("[1,]").is_err());
+    }
+
+    /// The snapshot is shared by both key representations, so running the tests with and
+    /// without the `borrowed-keys` feature checks that they flatten identically.
+    #[test]
+    fn flatten_fixture() {
+        let bump = Bump::new();
+        let root = Parser::new(include_str!("../../test_data/log.json"))
+            .parse(&bump)
+            .unwrap();
+
+        #[cfg(feature = "borrowed-keys")]
+        let _: &str = root.as_object().unwrap()[0].key;
+
+        insta::assert_debug_snapshot!(root.flattened());
+    }
+
+    #[test]
+    fn fill_template_placeholders() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let template = parse(
+            r#"{"db": {"host": "${db.host}", "ports": ["${db.port}", 5433]}, "name": "app", "debug": "${debug}", "raw": "$db.host", "partial": "x${db.host}"}"#,
+        );
+        let values = BTreeMap::from(
+            [("db.host", "localhost"), ("db.port", "5432")]
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+
+        assert_eq!(
+            template.fill_template(&values, &bump),
+            parse(
+                r#"{"db": {"host": "localhost", "ports": ["5432", 5433]}, "name": "app", "debug": "${debug}", "raw": "$db.host", "partial": "x${db.host}"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn mark_empty_containers() {
+        let json = r#"{"a": {}, "b": [], "c": [1, {}], "d": {"e": []}, "f": "x"}"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+
+        assert_eq!(
+            root.flattened().into_iter().collect::<std::vec::Vec<_>>(),
+            [("c.000", "1"), ("f", "x")].map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+
+        let options = FlattenOptions {
+            mark_empty_containers: true,
+            ..Default::default()
+        };
+        let flat = root.flattened_with(&options);
+
+        assert_eq!(
+            flat.iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect::<std::vec::Vec<_>>(),
+            [
+                ("a", "{}"),
+                ("b", "[]"),
+                ("c.000", "1"),
+                ("c.001", "{}"),
+                ("d.e", "[]"),
+                ("f", "x"),
+            ]
+        );
+        assert_eq!(
+            JsonValue::unflatten(&flat, &options, &bump),
+            Parser::new(r#"{"a": {}, "b": [], "c": ["1", {}], "d": {"e": []}, "f": "x"}"#)
+                .parse(&bump)
+                .unwrap()
+        );
+        assert_eq!(
+            JsonValue::Array(bumpalo::vec![in &bump]).flattened_with(&options),
+            BTreeMap::from([(String::new(), "[]".to_string())])
+        );
+    }
+
+    #[test]
+    fn flatten_very_deep_value() {
+        let bump = Bump::new();
+        let mut value = JsonValue::Boolean(true);
+
+        for depth in 0..100_000 {
+            value = if depth % 2 == 0 {
+                JsonValue::Array(vec![in &bump; value])
+            } else {
+                JsonValue::Object(vec![in &bump; JsonProperty::from(("k", value))])
+            };
+        }
+
+        let flat = value.flattened_with(&FlattenOptions {
+            index_width: 1,
+            ..Default::default()
+        });
+        let (key, leaf) = flat.first_key_value().unwrap();
+
+        assert_eq!(flat.len(), 1);
+        assert_eq!(leaf, "true");
+        assert_eq!(key, &["k", "0"].repeat(50_000).join("."));
+
+        // Dropping recurses through the nested vectors, and the arena frees them anyway
+        std::mem::forget(value);
+    }
+}
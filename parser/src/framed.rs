@@ -0,0 +1,194 @@
+use std::io::{self, Read};
+
+use crate::{
+    boxed::{BoxedJson, parse_boxed},
+    error::ExpectedTokenError,
+};
+
+/// Why a frame read by [`FramedReader`] couldn't be turned into a value.
+#[derive(Debug)]
+pub enum FrameError {
+    /// Reading failed, or the stream ended partway through a frame.
+    Io(io::Error),
+    /// The frame isn't valid UTF-8.
+    Utf8(std::string::FromUtf8Error),
+    /// The frame isn't valid JSON.
+    Parse(ExpectedTokenError),
+}
+
+impl std::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::Io(e) => write!(f, "reading frame: {e}"),
+            FrameError::Utf8(e) => write!(f, "frame is not UTF-8: {e}"),
+            FrameError::Parse(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+/// Reads a stream of JSON messages that are each preceded by their length in bytes as a
+/// 4-byte big-endian integer, yielding one value per frame.
+///
+/// Iteration ends cleanly when the stream ends between frames. After an I/O error, including
+/// the stream ending partway through a frame, nothing more is read. A frame that isn't valid
+/// JSON is reported and skipped.
+#[derive(Debug)]
+pub struct FramedReader<R> {
+    reader: R,
+    done: bool,
+}
+
+impl<R: Read> FramedReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            done: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the length prefix, returning `None` if the stream ends before its first byte.
+    fn read_len(&mut self) -> io::Result<Option<u32>> {
+        let mut buf = [0; 4];
+        let mut filled = 0;
+
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) if filled == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(Some(u32::from_be_bytes(buf)))
+    }
+
+    fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(len) = self.read_len()? else {
+            return Ok(None);
+        };
+
+        // Reading through `take` rather than allocating `len` bytes up front means a corrupt
+        // length can't cause a huge allocation
+        let mut body = Vec::new();
+
+        (&mut self.reader).take(len.into()).read_to_end(&mut body)?;
+
+        if body.len() < len as usize {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(Some(body))
+    }
+}
+
+impl<R: Read> Iterator for FramedReader<R> {
+    type Item = Result<BoxedJson, FrameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let body = match self.read_frame() {
+            Ok(Some(body)) => body,
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Err(e) => {
+                self.done = true;
+                return Some(Err(FrameError::Io(e)));
+            }
+        };
+
+        let input = match String::from_utf8(body) {
+            Ok(input) => input,
+            Err(e) => return Some(Err(FrameError::Utf8(e))),
+        };
+
+        Some(parse_boxed(&input).map_err(FrameError::Parse))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn frame(json: &str) -> Vec<u8> {
+        let mut bytes = (json.len() as u32).to_be_bytes().to_vec();
+
+        bytes.extend_from_slice(json.as_bytes());
+        bytes
+    }
+
+    /// Returns at most one byte per read, to split every length and body.
+    struct Trickle<R>(R);
+
+    impl<R: Read> Read for Trickle<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(1);
+
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn two_frames() {
+        let mut stream = frame(r#"{"id": 1, "tags": ["a"]}"#);
+
+        stream.extend(frame("[true, null]"));
+
+        let values = FramedReader::new(Cursor::new(stream.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(values.len(), 2);
+        assert_eq!(
+            values[0].get("tags").and_then(|tags| tags.index(0)),
+            Some(&BoxedJson::String("a".to_owned()))
+        );
+        assert_eq!(
+            values[1],
+            BoxedJson::Array(Box::new([BoxedJson::Boolean(true), BoxedJson::Null]))
+        );
+
+        let trickled = FramedReader::new(Trickle(Cursor::new(stream)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(trickled, values);
+    }
+
+    #[test]
+    fn bad_frames() {
+        let mut stream = frame("[1,]");
+
+        stream.extend(frame("2"));
+        stream.extend(&frame("{}")[..4]);
+
+        let mut reader = FramedReader::new(Cursor::new(stream));
+
+        assert!(matches!(reader.next(), Some(Err(FrameError::Parse(_)))));
+        assert_eq!(reader.next().unwrap().unwrap(), BoxedJson::Number(2.0));
+        assert!(matches!(
+            reader.next(),
+            Some(Err(FrameError::Io(e))) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+        assert!(reader.next().is_none());
+
+        let mut truncated = FramedReader::new(Cursor::new([0, 0]));
+
+        assert!(matches!(truncated.next(), Some(Err(FrameError::Io(_)))));
+        assert!(FramedReader::new(Cursor::new([])).next().is_none());
+    }
+}
@@ -1,6 +1,23 @@
 #![feature(allocator_api)]
 
 pub mod ast;
+pub mod batch;
+pub mod boxed;
+#[cfg(test)]
+mod conformance;
+pub mod decode;
+pub mod document;
 pub mod error;
+pub mod fingerprint;
+pub mod flatten;
+pub mod framed;
+pub mod merge;
+pub mod numbers;
+pub mod options;
 pub mod parser;
+pub mod pointer;
+pub mod pool;
+pub mod recover;
+pub mod source;
 pub mod token;
+pub mod validate;
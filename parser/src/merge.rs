@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+
+use bumpalo::{Bump, collections::Vec};
+
+use crate::{
+    ast::{JsonProperty, JsonValue},
+    source::SourceRecorder,
+};
+
+/// Which value [`JsonValue::merge_objects`] keeps when several objects share a key.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// The value from the first object containing the key.
+    KeepFirst,
+    /// The value from the last object containing the key.
+    #[default]
+    KeepLast,
+}
+
+/// Two different values found at the same place by [`JsonValue::merge_tracked`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict<'a> {
+    /// JSON Pointer to the value.
+    pub pointer: String,
+    /// The value from the object `merge_tracked` was called on.
+    pub left: JsonValue<'a>,
+    /// The value from the other object, which is the one kept in the merged result.
+    pub right: JsonValue<'a>,
+}
+
+impl<'a> JsonValue<'a> {
+    /// Concatenates the elements of several arrays into one, such as the pages of a paginated
+    /// response. Values that aren't arrays are appended as single elements.
+    pub fn concat_arrays(values: &[JsonValue<'a>], bump: &'a Bump) -> JsonValue<'a> {
+        let len = values
+            .iter()
+            .map(|value| value.as_array().map_or(1, |json_values| json_values.len()))
+            .sum();
+
+        let mut items = Vec::with_capacity_in(len, bump);
+
+        for value in values {
+            match value {
+                JsonValue::Array(json_values) => items.extend(json_values.iter().cloned()),
+                _ => items.push(value.clone()),
+            }
+        }
+
+        JsonValue::Array(items)
+    }
+
+    /// Splits an array into `n` arrays whose lengths differ by at most one, keeping the
+    /// elements in order, so that [`JsonValue::concat_arrays`] reassembles it. A value that
+    /// isn't an array is returned as the only shard, and `n` of zero is treated as one.
+    pub fn shard_array(&self, n: usize, bump: &'a Bump) -> std::vec::Vec<JsonValue<'a>> {
+        let JsonValue::Array(json_values) = self else {
+            return std::vec![self.clone()];
+        };
+
+        let n = n.max(1);
+        let (len, extra) = (json_values.len() / n, json_values.len() % n);
+        let mut remaining = json_values.as_slice();
+
+        (0..n)
+            .map(|shard| {
+                let (items, rest) = remaining.split_at(len + usize::from(shard < extra));
+
+                remaining = rest;
+
+                JsonValue::Array(Vec::from_iter_in(items.iter().cloned(), bump))
+            })
+            .collect()
+    }
+
+    /// Merges the properties of several objects into one, resolving keys that appear more
+    /// than once with `strategy`. Keys keep the position of their first occurrence and values
+    /// that aren't objects are skipped.
+    pub fn merge_objects(
+        values: &[JsonValue<'a>],
+        strategy: MergeStrategy,
+        bump: &'a Bump,
+    ) -> JsonValue<'a> {
+        let mut items: Vec<JsonProperty<'a>> = Vec::new_in(bump);
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+
+        for property in values.iter().filter_map(JsonValue::as_object).flatten() {
+            match positions.entry(property.key_str()) {
+                Entry::Occupied(entry) if strategy == MergeStrategy::KeepLast => {
+                    items[*entry.get()].value = property.value.clone();
+                }
+                Entry::Occupied(_) => (),
+                Entry::Vacant(entry) => {
+                    entry.insert(items.len());
+                    items.push(property.clone());
+                }
+            }
+        }
+
+        JsonValue::Object(items)
+    }
+
+    /// Deeply merges `other` into this value, recording a [`Conflict`] wherever both have
+    /// a value for the same key that differs and can't be merged, so that disagreements can
+    /// be reviewed rather than silently resolved.
+    ///
+    /// Objects on both sides are merged key by key, with keys from this value first. Anything
+    /// else, including arrays, is only merged if equal. The merged result takes `other`'s side
+    /// of each conflict, like [`MergeStrategy::KeepLast`].
+    pub fn merge_tracked(
+        &self,
+        other: &JsonValue<'a>,
+        bump: &'a Bump,
+    ) -> (JsonValue<'a>, std::vec::Vec<Conflict<'a>>) {
+        let mut conflicts = std::vec::Vec::new();
+        let merged = merge_tracked_at(
+            self,
+            other,
+            bump,
+            &mut SourceRecorder::path_only(),
+            &mut conflicts,
+        );
+
+        (merged, conflicts)
+    }
+}
+
+fn merge_tracked_at<'a>(
+    left: &JsonValue<'a>,
+    right: &JsonValue<'a>,
+    bump: &'a Bump,
+    path: &mut SourceRecorder,
+    conflicts: &mut std::vec::Vec<Conflict<'a>>,
+) -> JsonValue<'a> {
+    let (JsonValue::Object(left_properties), JsonValue::Object(right_properties)) = (left, right)
+    else {
+        if left != right {
+            conflicts.push(Conflict {
+                pointer: path.path().to_owned(),
+                left: left.clone(),
+                right: right.clone(),
+            });
+        }
+
+        return right.clone();
+    };
+
+    // The last occurrence of a repeated key wins, as with `JsonValue::pointer`
+    let right_values: HashMap<&str, &JsonValue<'a>> = right_properties
+        .iter()
+        .map(|property| (property.key_str(), &property.value))
+        .collect();
+
+    let mut items = Vec::with_capacity_in(left_properties.len(), bump);
+
+    for property in left_properties.iter() {
+        let value = match right_values.get(property.key_str()) {
+            Some(right_value) => {
+                let len = path.push_key(property.key_str());
+                let merged = merge_tracked_at(&property.value, right_value, bump, path, conflicts);
+
+                path.pop(len);
+                merged
+            }
+            None => property.value.clone(),
+        };
+
+        items.push(JsonProperty {
+            value,
+            ..property.clone()
+        });
+    }
+
+    let left_keys: HashSet<&str> = left_properties
+        .iter()
+        .map(|property| property.key_str())
+        .collect();
+
+    for property in right_properties.iter() {
+        if !left_keys.contains(property.key_str()) {
+            items.push(property.clone());
+        }
+    }
+
+    JsonValue::Object(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn concat_three_arrays() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let pages = [parse("[1, 2]"), parse("[]"), parse(r#"[{"id": 3}, [4]]"#)];
+
+        assert_eq!(
+            JsonValue::concat_arrays(&pages, &bump),
+            parse(r#"[1, 2, {"id": 3}, [4]]"#)
+        );
+        assert_eq!(
+            JsonValue::concat_arrays(&[parse("[1]"), parse("null")], &bump),
+            parse("[1, null]")
+        );
+    }
+
+    #[test]
+    fn shard_ten_elements_three_ways() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let value = parse("[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+        let shards = value.shard_array(3, &bump);
+
+        assert_eq!(
+            shards,
+            [
+                parse("[0, 1, 2, 3]"),
+                parse("[4, 5, 6]"),
+                parse("[7, 8, 9]")
+            ]
+        );
+        assert_eq!(JsonValue::concat_arrays(&shards, &bump), value);
+
+        assert_eq!(value.shard_array(0, &bump), std::slice::from_ref(&value));
+        assert_eq!(
+            parse("[1]").shard_array(2, &bump),
+            [parse("[1]"), parse("[]")]
+        );
+        assert_eq!(
+            parse(r#"{"a": 1}"#).shard_array(3, &bump),
+            [parse(r#"{"a": 1}"#)]
+        );
+    }
+
+    #[test]
+    fn merge_three_objects() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let shards = [
+            parse(r#"{"a": 1, "b": {"x": 1}}"#),
+            parse(r#"{"c": 3, "a": 2}"#),
+            parse(r#"{"b": {"y": 2}, "d": null}"#),
+        ];
+
+        assert_eq!(
+            JsonValue::merge_objects(&shards, MergeStrategy::KeepLast, &bump),
+            parse(r#"{"a": 2, "b": {"y": 2}, "c": 3, "d": null}"#)
+        );
+        assert_eq!(
+            JsonValue::merge_objects(&shards, MergeStrategy::KeepFirst, &bump),
+            parse(r#"{"a": 1, "b": {"x": 1}, "c": 3, "d": null}"#)
+        );
+    }
+
+    #[test]
+    fn merge_tracked_records_conflicts() {
+        let bump = Bump::new();
+        let parse = |json| Parser::new(json).parse(&bump).unwrap();
+
+        let crm = parse(
+            r#"{"id": 7, "name": "Ann", "address": {"city": "Leeds", "zip": "LS1"}, "tags": [1]}"#,
+        );
+        let billing = parse(
+            r#"{"id": 7, "address": {"city": "York", "country": "UK"}, "tags": [1], "vip": true}"#,
+        );
+
+        let (merged, conflicts) = crm.merge_tracked(&billing, &bump);
+
+        assert_eq!(
+            merged,
+            parse(
+                r#"{"id": 7, "name": "Ann", "address": {"city": "York", "zip": "LS1", "country": "UK"}, "tags": [1], "vip": true}"#
+            )
+        );
+        assert_eq!(
+            conflicts,
+            [Conflict {
+                pointer: "/address/city".to_owned(),
+                left: JsonValue::String("Leeds"),
+                right: JsonValue::String("York"),
+            }]
+        );
+
+        // Values that can't be merged conflict as a whole, even when both are arrays
+        let (_, conflicts) = parse(r#"{"a/b": [1], "c": {"d": 1}}"#)
+            .merge_tracked(&parse(r#"{"a/b": [2], "c": 1}"#), &bump);
+
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|conflict| conflict.pointer.as_str())
+                .collect::<std::vec::Vec<_>>(),
+            ["/a~1b", "/c"]
+        );
+        assert!(crm.merge_tracked(&crm, &bump).1.is_empty());
+    }
+}
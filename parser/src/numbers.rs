@@ -0,0 +1,216 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    rc::Rc,
+};
+
+use bumpalo::Bump;
+
+use crate::{ast::JsonValue, error::ExpectedTokenError, parser::Parser, token::Span};
+
+/// Why [`Parser::parse_with_numbers`] failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberError {
+    /// The input isn't valid JSON.
+    Parse(ExpectedTokenError),
+    /// The number parser rejected the literal at `pointer`, explaining why in `message`.
+    Rejected {
+        pointer: String,
+        span: Span,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for NumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumberError::Parse(e) => write!(f, "{e}"),
+            NumberError::Rejected {
+                pointer, message, ..
+            } => write!(f, "number at '{pointer}' rejected: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for NumberError {}
+
+impl From<ExpectedTokenError> for NumberError {
+    fn from(e: ExpectedTokenError) -> Self {
+        NumberError::Parse(e)
+    }
+}
+
+/// Called by the parser with the JSON Pointer, span and source text of each number, returning
+/// whether to carry on parsing.
+pub(crate) struct NumberHook<'a>(Box<HookFn<'a>>);
+
+type HookFn<'a> = dyn FnMut(&str, Span, &str) -> bool + 'a;
+
+impl NumberHook<'_> {
+    pub(crate) fn call(&mut self, pointer: &str, span: Span, literal: &str) -> bool {
+        (self.0)(pointer, span, literal)
+    }
+}
+
+impl std::fmt::Debug for NumberHook<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("NumberHook")
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Parses the input like [`Parser::parse`], additionally passing the source text of every
+    /// number to `parse_number` as it is parsed, such as to read it as fixed-point rather
+    /// than `f64`.
+    ///
+    /// The results are returned keyed by the JSON Pointer of each number, while the value
+    /// itself still holds the `f64`. Numbers under a key that an object repeats are all
+    /// passed to `parse_number` too, so the results may include pointers that a later member
+    /// replaced, and keep the last number at each pointer. Fails at the first number, in
+    /// document order, that `parse_number` rejects.
+    pub fn parse_with_numbers<N: 'a>(
+        self,
+        bump: &'a Bump,
+        mut parse_number: impl FnMut(&str) -> Result<N, String> + 'a,
+    ) -> Result<(JsonValue<'a>, BTreeMap<String, N>), NumberError> {
+        let numbers = Rc::new(RefCell::new(BTreeMap::new()));
+        let rejected = Rc::new(Cell::new(None));
+
+        let hook = {
+            let numbers = Rc::clone(&numbers);
+            let rejected = Rc::clone(&rejected);
+
+            NumberHook(Box::new(
+                move |pointer: &str, span, literal: &str| match parse_number(literal) {
+                    Ok(number) => {
+                        numbers.borrow_mut().insert(pointer.to_owned(), number);
+                        true
+                    }
+                    Err(message) => {
+                        rejected.set(Some(NumberError::Rejected {
+                            pointer: pointer.to_owned(),
+                            span,
+                            message,
+                        }));
+                        false
+                    }
+                },
+            ))
+        };
+
+        let value = self.with_number_hook(hook).parse(bump);
+
+        // The parser stops as soon as a number is rejected, so a rejection is what failed it
+        if let Some(rejected) = rejected.take() {
+            return Err(rejected);
+        }
+
+        Ok((value?, numbers.take()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads a decimal with at most two places as a whole number of hundredths.
+    fn cents(literal: &str) -> Result<i64, String> {
+        let (whole, fraction) = literal.split_once('.').unwrap_or((literal, ""));
+
+        if fraction.len() > 2 || literal.contains(['e', 'E']) {
+            return Err(format!("{literal} has more than two decimal places"));
+        }
+
+        let whole: i64 = whole
+            .parse()
+            .map_err(|_| format!("{literal} is out of range"))?;
+        let fraction: i64 = format!("{fraction:0<2}").parse().unwrap();
+
+        let sign = if literal.starts_with('-') { -1 } else { 1 };
+
+        Ok(whole * 100 + sign * fraction)
+    }
+
+    #[test]
+    fn fixed_point_numbers() {
+        let bump = Bump::new();
+        let (value, numbers) = Parser::new(r#"{"price": 12.5, "items": [{"cost": 0.99}, 3]}"#)
+            .parse_with_numbers(&bump, cents)
+            .unwrap();
+
+        assert_eq!(value.pointer("/price"), Some(&JsonValue::Number(12.5)));
+        assert_eq!(
+            numbers,
+            BTreeMap::from([
+                ("/items/0/cost".to_owned(), 99),
+                ("/items/1".to_owned(), 300),
+                ("/price".to_owned(), 1250),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejected_number() {
+        let bump = Bump::new();
+        let err = Parser::new(r#"{"a": 1.25, "b": [1.005, 2.001]}"#)
+            .parse_with_numbers(&bump, cents)
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            NumberError::Rejected {
+                pointer: "/b/0".to_owned(),
+                span: Span { start: 18, end: 23 },
+                message: "1.005 has more than two decimal places".to_owned(),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "number at '/b/0' rejected: 1.005 has more than two decimal places"
+        );
+
+        assert!(matches!(
+            Parser::new("[1,]").parse_with_numbers(&bump, cents),
+            Err(NumberError::Parse(_))
+        ));
+
+        // Rejected before the error after it is reached
+        assert!(matches!(
+            Parser::new("[1.005,]").parse_with_numbers(&bump, cents),
+            Err(NumberError::Rejected { .. })
+        ));
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let bump = Bump::new();
+
+        // A number replaced by a later member with the same key is still checked
+        let err = Parser::new(r#"{"a": 1.001, "a": "x"}"#)
+            .parse_with_numbers(&bump, cents)
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            NumberError::Rejected {
+                pointer: "/a".to_owned(),
+                span: Span { start: 6, end: 11 },
+                message: "1.001 has more than two decimal places".to_owned(),
+            }
+        );
+
+        let (value, numbers) = Parser::new(r#"{"a": 1.5, "b": {"c": 1}, "a": 2, "b": 3}"#)
+            .parse_with_numbers(&bump, cents)
+            .unwrap();
+
+        assert_eq!(value.pointer("/a"), Some(&JsonValue::Number(2.0)));
+        assert_eq!(
+            numbers,
+            BTreeMap::from([
+                ("/a".to_owned(), 200),
+                ("/b".to_owned(), 300),
+                ("/b/c".to_owned(), 100),
+            ])
+        );
+    }
+}
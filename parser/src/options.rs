@@ -0,0 +1,898 @@
+/// The default for [`ParserOptions::max_depth`], far deeper than real documents nest while
+/// leaving plenty of stack to spare.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Controls which extensions to strict JSON the [`Parser`] accepts.
+///
+/// The default is strict JSON.
+///
+/// [`Parser`]: crate::parser::Parser
+#[derive(Debug, Clone, Copy)]
+pub struct ParserOptions {
+    /// Treat `//` line comments and `/* */` block comments as whitespace. Comments directly
+    /// preceding an object key are kept in [`JsonProperty::leading_comment`].
+    ///
+    /// [`JsonProperty::leading_comment`]: crate::ast::JsonProperty::leading_comment
+    pub allow_comments: bool,
+    /// Accept a `,` after the last element of an array or the last member of an object.
+    pub allow_trailing_commas: bool,
+    /// Maximum number of values, counting scalars, objects and arrays, that a document may
+    /// contain. Parsing stops with an error at the first value over the limit.
+    pub max_values: Option<usize>,
+    /// Maximum length in bytes of a number or string literal, excluding the quotes. Longer
+    /// literals are rejected by the lexer before any further validation or number parsing.
+    pub max_token_len: Option<usize>,
+    /// Maximum number of objects and arrays that may be entered over the whole document,
+    /// however they are nested. Bounds the work done on documents that are both wide and deep.
+    pub recursion_budget: Option<usize>,
+    /// Maximum depth to which objects and arrays may be nested, counting the root container
+    /// as depth 1. Parsing stops with an error at the first container nested deeper, instead
+    /// of recursing until the stack overflows. Defaults to [`DEFAULT_MAX_DEPTH`].
+    pub max_depth: Option<usize>,
+    /// Maximum number of members that any one object may have. The error for an object over
+    /// the limit is reported at its opening `{`.
+    pub max_object_members: Option<usize>,
+    /// Maximum number of elements that any one array may have. The error for an array over
+    /// the limit is reported at its opening `[`.
+    pub max_array_elements: Option<usize>,
+    /// Capacity to allocate up front for each non-empty array instead of the default of 8.
+    /// Avoids repeatedly growing arrays when the input is known to hold very large ones.
+    pub array_capacity_hint: Option<usize>,
+    /// Accept the non-standard `\xHH` (two hex digits) and `\0` escapes in strings, decoded
+    /// to the character with that code point.
+    pub relaxed_escapes: bool,
+    /// Accept zero-padded numbers such as `007`, parsed as `7`.
+    pub allow_leading_zeros: bool,
+    /// Accept the JavaScript literals `NaN`, `Infinity` and `-Infinity` as numbers. Standard
+    /// JSON has no way to write them, see [`NonFinite`] for the choices when serializing.
+    pub allow_non_finite: bool,
+    /// Parse strings whose entire contents are a valid JSON number, such as `"30"`, as that
+    /// number.
+    pub coerce_numeric_strings: bool,
+    /// Reject documents whose root is a scalar such as `42` rather than an object or array.
+    pub require_container_root: bool,
+    /// Parse the JSON inside a JSONP callback such as `cb({...});` or a JavaScript assignment
+    /// such as `var x = {...};`, ignoring the wrapper. Input without a wrapper parses as
+    /// usual.
+    pub strip_js_wrapper: bool,
+    /// Reject integers too large for an `f64` to represent exactly, such as IDs beyond 2^53,
+    /// instead of silently rounding them.
+    pub reject_precision_loss: bool,
+    /// Called with every object key after decoding its escapes. Parsing fails at the first
+    /// key for which it returns `false`.
+    ///
+    /// Options compare equal when both or neither have a validator. The functions themselves
+    /// aren't compared, since the same function can have different addresses.
+    pub validate_keys: Option<fn(&str) -> bool>,
+}
+
+impl PartialEq for ParserOptions {
+    fn eq(&self, other: &Self) -> bool {
+        // Destructured so that a new field can't be left out of the comparison
+        let Self {
+            allow_comments,
+            allow_trailing_commas,
+            max_values,
+            max_token_len,
+            recursion_budget,
+            max_depth,
+            max_object_members,
+            max_array_elements,
+            array_capacity_hint,
+            relaxed_escapes,
+            allow_leading_zeros,
+            allow_non_finite,
+            coerce_numeric_strings,
+            require_container_root,
+            strip_js_wrapper,
+            reject_precision_loss,
+            validate_keys,
+        } = *self;
+
+        allow_comments == other.allow_comments
+            && allow_trailing_commas == other.allow_trailing_commas
+            && max_values == other.max_values
+            && max_token_len == other.max_token_len
+            && recursion_budget == other.recursion_budget
+            && max_depth == other.max_depth
+            && max_object_members == other.max_object_members
+            && max_array_elements == other.max_array_elements
+            && array_capacity_hint == other.array_capacity_hint
+            && relaxed_escapes == other.relaxed_escapes
+            && allow_leading_zeros == other.allow_leading_zeros
+            && allow_non_finite == other.allow_non_finite
+            && coerce_numeric_strings == other.coerce_numeric_strings
+            && require_container_root == other.require_container_root
+            && strip_js_wrapper == other.strip_js_wrapper
+            && reject_precision_loss == other.reject_precision_loss
+            && validate_keys.is_some() == other.validate_keys.is_some()
+    }
+}
+
+impl Eq for ParserOptions {}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self::strict_rfc8259()
+    }
+}
+
+impl ParserOptions {
+    /// Strict RFC 8259 JSON with every extension turned off, for conformance testing.
+    ///
+    /// Spec checks such as rejecting unescaped control characters are always enforced and
+    /// aren't affected by any option.
+    pub fn strict_rfc8259() -> Self {
+        Self {
+            allow_comments: false,
+            allow_trailing_commas: false,
+            max_values: None,
+            max_token_len: None,
+            recursion_budget: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_object_members: None,
+            max_array_elements: None,
+            array_capacity_hint: None,
+            relaxed_escapes: false,
+            allow_leading_zeros: false,
+            allow_non_finite: false,
+            coerce_numeric_strings: false,
+            require_container_root: false,
+            strip_js_wrapper: false,
+            reject_precision_loss: false,
+            validate_keys: None,
+        }
+    }
+
+    /// The dialect common in configuration files: JSON with comments and trailing commas,
+    /// and no other extensions.
+    pub fn config_friendly() -> Self {
+        Self {
+            allow_comments: true,
+            allow_trailing_commas: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// How [`JsonValue::write_json_with`] writes numbers that are NaN or infinite, which JSON
+/// can't represent.
+///
+/// [`JsonValue::write_json_with`]: crate::ast::JsonValue::write_json_with
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum NonFinite {
+    /// Fail with an [`io::ErrorKind::InvalidData`] error rather than write invalid JSON.
+    ///
+    /// [`io::ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    #[default]
+    Error,
+    /// Write `null`.
+    Null,
+    /// Write the JSON5 literals `NaN`, `Infinity` and `-Infinity`.
+    Json5Literal,
+}
+
+/// What [`JsonValue::write_json_pretty_with`] writes for each level of nesting.
+///
+/// [`JsonValue::write_json_pretty_with`]: crate::ast::JsonValue::write_json_pretty_with
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Indent {
+    /// The given number of spaces.
+    Spaces(usize),
+    /// A single tab.
+    Tab,
+}
+
+/// Controls how [`JsonValue::write_json_with`] serializes a value.
+///
+/// [`JsonValue::write_json_with`]: crate::ast::JsonValue::write_json_with
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// What to write for NaN and infinite numbers. The default is an error, since JSON has no
+    /// representation for them.
+    pub non_finite: NonFinite,
+    /// Write `/` in strings and keys as `\/`, so that the output can be embedded in an HTML
+    /// `<script>` element without a `</script>` inside a string ending it early.
+    pub escape_forward_slash: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::{
+        ast::JsonValue,
+        error::{ErrorContext, IllegalNumber, IllegalReason, IllegalString},
+        numbers::NumberError,
+        parser::Parser,
+        token::TokenKind,
+    };
+
+    fn accepts(options: ParserOptions, input: &str) -> bool {
+        let bump = Bump::new();
+
+        Parser::new_with_options(input, options)
+            .parse(&bump)
+            .is_ok()
+    }
+
+    #[test]
+    fn strict_rfc8259_test_suite_cases() {
+        let strict = ParserOptions::strict_rfc8259();
+
+        for accepted in [
+            "[]",
+            r#"{"a":[]}"#,
+            "42",
+            "[1E22]",
+            r#"["\"\\\/\b\f\n\r\t"]"#,
+            r#"["\u0060\u012a\u12AB"]"#,
+        ] {
+            assert!(accepts(strict, accepted), "y_ case rejected: {accepted}");
+        }
+
+        for rejected in [
+            r#"{"a":"b"}/**/"#,
+            r#"{"a":/*comment*/"b"}"#,
+            r#"["",]"#,
+            "[012]",
+            r#"["\x00"]"#,
+            "[1.]",
+        ] {
+            assert!(!accepts(strict, rejected), "n_ case accepted: {rejected}");
+        }
+
+        let mut jsonc = strict;
+        jsonc.allow_comments = true;
+
+        assert!(accepts(jsonc, r#"{"a":/*comment*/"b"}"#));
+    }
+
+    #[test]
+    fn max_values_limit() {
+        let limited = ParserOptions {
+            max_values: Some(100),
+            ..Default::default()
+        };
+
+        // The array itself counts as one value
+        let fits = format!("[{}]", ["0"; 99].join(","));
+        let too_many = format!("[{}]", ["0"; 100_000].join(","));
+
+        assert!(accepts(limited, &fits));
+        assert!(accepts(ParserOptions::default(), &too_many));
+
+        let bump = Bump::new();
+        let err = Parser::new_with_options(&too_many, limited)
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(err.context, Some(ErrorContext::TooManyValues(100)));
+        assert_eq!(err.invalid_col, 200);
+        assert_eq!(
+            err.to_string(),
+            "document has more than the maximum of 100 values at row 1 column 200"
+        );
+
+        // Nested containers count alongside their contents
+        let nested = r#"{"a": [[1, 2], {"b": [3]}]}"#;
+        let max = |max| ParserOptions {
+            max_values: Some(max),
+            ..Default::default()
+        };
+
+        assert!(accepts(max(8), nested));
+        assert!(!accepts(max(7), nested));
+    }
+
+    #[test]
+    fn relaxed_escapes() {
+        let relaxed = ParserOptions {
+            relaxed_escapes: true,
+            ..Default::default()
+        };
+
+        let decode = |options, input| {
+            let bump = Bump::new();
+            let value = Parser::new_with_options(input, options).parse(&bump);
+
+            value.map(|value| match value {
+                JsonValue::String(s) => Some(s.to_owned()),
+                _ => None,
+            })
+        };
+
+        assert_eq!(
+            decode(relaxed, r#""\x41\x7a\0!""#),
+            Ok(Some("Az\0!".to_owned()))
+        );
+        assert_eq!(decode(relaxed, r#""\xe9""#), Ok(Some("é".to_owned())));
+
+        for input in [r#""\x41""#, r#""\0""#] {
+            assert_eq!(
+                decode(ParserOptions::default(), input).unwrap_err().actual,
+                TokenKind::Illegal(Some(IllegalReason::String(IllegalString::InvalidEscape(2))))
+            );
+        }
+
+        for input in [r#""\xZZ""#, r#""\x4""#, r#""\x""#] {
+            assert_eq!(
+                decode(relaxed, input).unwrap_err().actual,
+                TokenKind::Illegal(Some(IllegalReason::String(IllegalString::InvalidEscape(2)))),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn allow_trailing_commas() {
+        let trailing = ParserOptions {
+            allow_trailing_commas: true,
+            ..Default::default()
+        };
+
+        let to_json = |options, input| {
+            let bump = Bump::new();
+
+            Parser::new_with_options(input, options)
+                .parse(&bump)
+                .map(|value| value.to_json_string().unwrap())
+        };
+
+        assert_eq!(to_json(trailing, "[1,2,]"), Ok("[1,2]".to_owned()));
+        assert_eq!(
+            to_json(trailing, r#"{"a":1,}"#),
+            Ok(r#"{"a":1}"#.to_owned())
+        );
+        assert_eq!(
+            to_json(ParserOptions::default(), "[1,2,]")
+                .unwrap_err()
+                .actual,
+            TokenKind::RBracket
+        );
+
+        for invalid in ["[,]", "{,}", "[1,,]"] {
+            assert!(to_json(trailing, invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn allow_leading_zeros() {
+        let padded = ParserOptions {
+            allow_leading_zeros: true,
+            ..Default::default()
+        };
+
+        let parse = |options, input| {
+            let bump = Bump::new();
+
+            Parser::new_with_options(input, options)
+                .parse(&bump)
+                .map(|value| match value {
+                    JsonValue::Number(number) => Some(number),
+                    _ => None,
+                })
+        };
+
+        assert_eq!(parse(padded, "007"), Ok(Some(7.0)));
+        assert_eq!(parse(padded, "-00.5"), Ok(Some(-0.5)));
+        assert_eq!(
+            parse(ParserOptions::default(), "007").unwrap_err().actual,
+            TokenKind::Illegal(Some(IllegalReason::Number(IllegalNumber::LeadingZero)))
+        );
+
+        for options in [padded, ParserOptions::default()] {
+            assert_eq!(parse(options, "0"), Ok(Some(0.0)));
+            assert_eq!(parse(options, "0.5"), Ok(Some(0.5)));
+        }
+    }
+
+    #[test]
+    fn allow_non_finite() {
+        let lenient = ParserOptions {
+            allow_non_finite: true,
+            reject_precision_loss: true,
+            ..Default::default()
+        };
+        let bump = Bump::new();
+        let input = "[NaN, Infinity, -Infinity, 1]";
+
+        let value = Parser::new_with_options(input, lenient)
+            .parse(&bump)
+            .unwrap();
+        let numbers = value.as_f64_vec().unwrap();
+
+        assert!(numbers[0].is_nan());
+        assert_eq!(numbers[1..], [f64::INFINITY, f64::NEG_INFINITY, 1.0]);
+
+        let json5 = SerializeOptions {
+            non_finite: NonFinite::Json5Literal,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            value.to_json_string_with(&json5).unwrap(),
+            "[NaN,Infinity,-Infinity,1]"
+        );
+        assert!(value.to_json_string_with(&Default::default()).is_err());
+
+        for rejected in [input, "NaN", "-Infinity"] {
+            assert!(!accepts(ParserOptions::default(), rejected), "{rejected}");
+        }
+
+        for invalid in [
+            "nan",
+            "inf",
+            "-NaN",
+            "+Infinity",
+            "Infinityx",
+            "-Infinity1",
+            "-Inf",
+        ] {
+            let err = Parser::new_with_options(invalid, lenient)
+                .parse(&bump)
+                .unwrap_err();
+
+            assert!(
+                matches!(err.actual, TokenKind::Illegal(_)),
+                "{invalid}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn coerce_numeric_strings() {
+        let coerce = ParserOptions {
+            coerce_numeric_strings: true,
+            ..Default::default()
+        };
+
+        let bump = Bump::new();
+        let parse = |options, input| Parser::new_with_options(input, options).parse(&bump);
+
+        assert_eq!(
+            parse(
+                coerce,
+                r#"{"age": "30", "ratio": "1.5", "exp": "-2e3", "id": "30abc", "pad": " 30", "lead": "007", "n": 4}"#
+            ),
+            parse(
+                ParserOptions::default(),
+                r#"{"age": 30, "ratio": 1.5, "exp": -2e3, "id": "30abc", "pad": " 30", "lead": "007", "n": 4}"#
+            )
+        );
+        assert_eq!(
+            parse(ParserOptions::default(), r#""30""#),
+            Ok(JsonValue::String("30"))
+        );
+        assert!(
+            parse(coerce, r#"["", "-", "1.", "NaN"]"#)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .all(|value| matches!(value, JsonValue::String(_)))
+        );
+    }
+
+    #[test]
+    fn require_container_root() {
+        let containers_only = ParserOptions {
+            require_container_root: true,
+            ..Default::default()
+        };
+
+        for scalar in ["true", "42", r#""x""#, "null"] {
+            assert!(accepts(ParserOptions::default(), scalar));
+            assert!(!accepts(containers_only, scalar));
+        }
+
+        assert!(accepts(containers_only, r#"{"a": 1}"#));
+        assert!(accepts(containers_only, "[true]"));
+
+        let bump = Bump::new();
+        let err = Parser::new_with_options("  42", containers_only)
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(err.context, Some(ErrorContext::ContainerRoot));
+        assert_eq!(err.expected, [TokenKind::LBrace, TokenKind::LBracket]);
+        assert_eq!(
+            err.to_string(),
+            "expected an object or array as the root at row 1 column 3 but got 'NUMBER' instead"
+        );
+    }
+
+    #[test]
+    fn strip_js_wrapper() {
+        let unwrap = ParserOptions {
+            strip_js_wrapper: true,
+            ..Default::default()
+        };
+        let bump = Bump::new();
+        let parse = |input| Parser::new_with_options(input, unwrap).parse(&bump);
+
+        let expected = Parser::new(r#"{"id": 1, "tags": ["a"]}"#)
+            .parse(&bump)
+            .unwrap();
+
+        for wrapped in [
+            r#"callback({"id": 1, "tags": ["a"]});"#,
+            r#"  jQuery.cb_1 ( {"id": 1, "tags": ["a"]} )"#,
+            r#"var x = {"id": 1, "tags": ["a"]};"#,
+            "const data={\"id\": 1, \"tags\": [\"a\"]}\n",
+        ] {
+            assert_eq!(parse(wrapped).unwrap(), expected, "{wrapped}");
+            assert!(!accepts(ParserOptions::default(), wrapped));
+        }
+
+        assert_eq!(
+            parse("[1, 2]").unwrap(),
+            Parser::new("[1, 2]").parse(&bump).unwrap()
+        );
+        assert_eq!(parse("true").unwrap(), JsonValue::Boolean(true));
+
+        for invalid in ["cb({});x", "cb({}", "var = {};", "1cb({})", "cb()"] {
+            assert!(parse(invalid).is_err(), "{invalid}");
+        }
+
+        // Positions still refer to the whole input
+        let err = parse("cb([1, ]);").unwrap_err();
+
+        assert_eq!((err.invalid_row, err.invalid_col), (1, 8));
+    }
+
+    #[test]
+    fn reject_precision_loss() {
+        let exact = ParserOptions {
+            reject_precision_loss: true,
+            ..Default::default()
+        };
+
+        for input in ["12345678901234567", "[-9007199254740993]", "1e400000"] {
+            assert!(accepts(ParserOptions::default(), input));
+        }
+
+        for input in [
+            "123456789012345",
+            "9007199254740992",
+            "-9007199254740992",
+            "18014398509481984",
+            "99999999999999991611392",
+            "0.1",
+            "1e400000",
+        ] {
+            assert!(accepts(exact, input), "{input}");
+        }
+
+        let overflows = format!("1{}", "0".repeat(400));
+
+        for input in [
+            "9007199254740993",
+            "[-9007199254740993]",
+            "100000000000000000000000",
+            &overflows,
+        ] {
+            assert!(!accepts(exact, input), "{input}");
+        }
+
+        let bump = Bump::new();
+        let err = Parser::new_with_options(r#"{"id": 12345678901234567}"#, exact)
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(err.context, Some(ErrorContext::PrecisionLoss));
+        assert_eq!(err.origin, "12345678901234567");
+        assert_eq!(
+            err.to_string(),
+            "integer can't be represented exactly as an f64 at row 1 column 8"
+        );
+    }
+
+    #[test]
+    fn validate_keys() {
+        let snake_case = ParserOptions {
+            validate_keys: Some(|key| {
+                !key.is_empty() && key.chars().all(|ch| ch.is_ascii_lowercase() || ch == '_')
+            }),
+            ..Default::default()
+        };
+
+        assert!(accepts(
+            snake_case,
+            r#"{"user_id": 1, "tags": {"is_admin": true}}"#
+        ));
+        assert!(accepts(snake_case, r#"["", {}]"#));
+        assert!(accepts(
+            ParserOptions::default(),
+            r#"{"": 1, "user-id": 2}"#
+        ));
+
+        let bump = Bump::new();
+
+        for (input, col) in [(r#"{"": 1}"#, 2), (r#"{"ok": {"user-id": 2}}"#, 9)] {
+            let err = Parser::new_with_options(input, snake_case)
+                .parse(&bump)
+                .unwrap_err();
+
+            assert_eq!(err.context, Some(ErrorContext::InvalidKey));
+            assert_eq!((err.invalid_row, err.invalid_col), (1, col));
+        }
+
+        let err = Parser::new_with_options(r#"{"user-id": 2}"#, snake_case)
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(err.origin, "user-id");
+        assert_eq!(err.span.start, 1);
+        assert_eq!(
+            err.to_string(),
+            "object key rejected by validate_keys at row 1 column 2"
+        );
+
+        // Validators aren't compared by address, only whether there is one
+        let other = ParserOptions {
+            validate_keys: Some(|key| !key.is_empty()),
+            ..Default::default()
+        };
+
+        assert_eq!(snake_case, other);
+        assert_ne!(snake_case, ParserOptions::default());
+    }
+
+    #[test]
+    fn config_friendly_preset() {
+        let config = r#"{
+    // Where to listen
+    "server": {
+        "host": "0.0.0.0",
+        "ports": [8080, 8443,], /* TLS on the second */
+    },
+    "features": ["metrics", "tracing",],
+}
+"#;
+
+        let bump = Bump::new();
+        let value = Parser::new_with_options(config, ParserOptions::config_friendly())
+            .parse(&bump)
+            .unwrap();
+
+        assert_eq!(
+            value.pointer("/server/ports/1"),
+            Some(&JsonValue::Number(8443.0))
+        );
+        assert_eq!(
+            value.as_object().unwrap()[1]
+                .value
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(!accepts(ParserOptions::strict_rfc8259(), config));
+        assert!(!accepts(ParserOptions::default(), config));
+
+        for trailing in ["[1,]", r#"{"a": 1,}"#, "[[],]"] {
+            assert!(accepts(ParserOptions::config_friendly(), trailing));
+            assert!(!accepts(ParserOptions::default(), trailing));
+        }
+
+        for invalid in ["[,]", "[1,,]", r#"{,}"#, "[1,]]", "{'a': 1}"] {
+            assert!(
+                !accepts(ParserOptions::config_friendly(), invalid),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn max_token_len_rejects_long_number() {
+        let limited = ParserOptions {
+            max_token_len: Some(1024),
+            ..Default::default()
+        };
+
+        let digits = "9".repeat(100_000);
+        let input = format!(r#"{{"n": {digits}.5e3, "s": "ok"}}"#);
+
+        // The lexer rejects the literal before it ever reaches the number parser
+        let bump = Bump::new();
+        let err = Parser::new_with_options(&input, limited)
+            .parse_with_numbers(&bump, |literal: &str| match literal.len() {
+                ..=1024 => Ok(()),
+                len => Err(format!("parsed a {len} byte literal")),
+            })
+            .unwrap_err();
+        let NumberError::Parse(err) = err else {
+            panic!("{err}");
+        };
+
+        assert_eq!(
+            err.actual,
+            TokenKind::Illegal(Some(IllegalReason::TooLong(1024)))
+        );
+        assert_eq!((err.span.start, err.span.end), (6, 100_010));
+        assert!(
+            err.to_string()
+                .ends_with("but got 'ILLEGAL (longer than the maximum of 1024 bytes)' instead")
+        );
+
+        assert!(accepts(limited, &format!("[{}]", "1".repeat(1024))));
+        assert!(!accepts(limited, &format!(r#"["{}"]"#, "a".repeat(1025))));
+    }
+
+    #[test]
+    fn recursion_budget_exhausted() {
+        let budget = |budget| ParserOptions {
+            recursion_budget: Some(budget),
+            ..Default::default()
+        };
+
+        // 50 sibling branches, each nested 10 deep, plus the root
+        let branch = format!("{}{}", "[".repeat(10), "]".repeat(10));
+        let wide_and_deep = format!("[{}]", vec![branch; 50].join(","));
+
+        assert!(accepts(budget(501), &wide_and_deep));
+        assert!(!accepts(budget(500), &wide_and_deep));
+        assert!(accepts(budget(1), &format!("[{}]", ["1"; 1000].join(","))));
+
+        let bump = Bump::new();
+        let err = Parser::new_with_options(&wide_and_deep, budget(500))
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(
+            err.context,
+            Some(ErrorContext::RecursionBudgetExhausted(500))
+        );
+        assert!(err.context.is_some_and(|context| context.is_limit()));
+        assert_eq!(err.span.start, wide_and_deep.len() - 12);
+    }
+
+    #[test]
+    fn max_depth_limit() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let bump = Bump::new();
+
+        assert!(accepts(
+            ParserOptions::default(),
+            &nested(DEFAULT_MAX_DEPTH)
+        ));
+        assert!(!accepts(
+            ParserOptions::default(),
+            &nested(DEFAULT_MAX_DEPTH + 1)
+        ));
+
+        // Closing a container frees up its level for the next sibling
+        let siblings = format!("[{},{}]", nested(127), nested(127));
+
+        assert!(accepts(ParserOptions::default(), &siblings));
+
+        let adversarial = "[".repeat(100_000);
+        let err = Parser::new(&adversarial).parse(&bump).unwrap_err();
+
+        assert_eq!(err.context, Some(ErrorContext::TooDeeplyNested(128)));
+        assert_eq!((err.invalid_col, err.span.start), (129, 128));
+        assert_eq!(
+            err.to_string(),
+            "nesting is deeper than the maximum of 128 levels at row 1 column 129"
+        );
+
+        let shallow = ParserOptions {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+
+        assert!(accepts(shallow, r#"{"a": [1], "b": {"c": 2}}"#));
+        assert!(!accepts(shallow, r#"{"a": [{}]}"#));
+        assert!(!accepts(shallow, r#"[{"a": []}]"#));
+
+        // Skipped values are limited too
+        let keep = std::collections::HashSet::from(["a"]);
+        let skipped = format!(r#"{{"a": 1, "b": {}}}"#, nested(DEFAULT_MAX_DEPTH));
+
+        assert_eq!(
+            crate::parser::parse_filtered(&skipped, &keep, &bump)
+                .unwrap_err()
+                .context,
+            Some(ErrorContext::TooDeeplyNested(128))
+        );
+
+        let unlimited = ParserOptions {
+            max_depth: None,
+            ..Default::default()
+        };
+
+        // Without a limit only the stack bounds the depth, so check the 500 levels of the
+        // conformance suite still fit in the 2 MiB that test threads get by default
+        std::thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(move || assert!(accepts(unlimited, &nested(500))))
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn container_limits() {
+        let limited = ParserOptions {
+            max_object_members: Some(2),
+            max_array_elements: Some(3),
+            ..Default::default()
+        };
+
+        // Limits apply to each container separately, not to the document as a whole
+        assert!(accepts(
+            limited,
+            r#"{"a": [1, 2, 3], "b": {"c": [4, 5, 6], "d": 7}}"#
+        ));
+        assert!(accepts(
+            ParserOptions {
+                allow_trailing_commas: true,
+                ..limited
+            },
+            "[1, 2, 3,]"
+        ));
+
+        let bump = Bump::new();
+        let json = "{\n  \"a\": 1,\n  \"b\": {\"c\": 1, \"d\": 2, \"e\": 3}\n}";
+        let object = Parser::new_with_options(json, limited)
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(object.context, Some(ErrorContext::TooManyObjectMembers(2)));
+        assert_eq!(object.actual, TokenKind::LBrace);
+        assert_eq!(object.span.start, 19);
+        assert_eq!(
+            object.to_string(),
+            "object has more than the maximum of 2 members at row 3 column 8"
+        );
+
+        let array = Parser::new_with_options(r#"{"a": [[1, 2, 3, 4]]}"#, limited)
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(array.context, Some(ErrorContext::TooManyArrayElements(3)));
+        assert_eq!(array.actual, TokenKind::LBracket);
+        assert_eq!((array.invalid_row, array.invalid_col), (1, 8));
+        assert!(array.context.is_some_and(|context| context.is_limit()));
+        assert_eq!(
+            array.to_string(),
+            "array has more than the maximum of 3 elements at row 1 column 8"
+        );
+
+        let elements = Parser::new_with_options("[1, 2, 3, 4]", limited)
+            .array_elements(&bump)
+            .collect::<Vec<_>>();
+
+        assert_eq!(elements.len(), 4);
+        assert!(elements[3].as_ref().is_err_and(|err| err.span.start == 0));
+    }
+
+    #[test]
+    fn array_capacity_hint_avoids_growth() {
+        let json = format!("[{}]", ["1.5"; 10_000].join(","));
+
+        let parse = |options| {
+            let bump = Bump::new();
+            let value = Parser::new_with_options(&json, options)
+                .parse(&bump)
+                .unwrap()
+                .to_json_string()
+                .unwrap();
+
+            (value, bump.allocated_bytes())
+        };
+
+        let (default, default_bytes) = parse(ParserOptions::default());
+        let (hinted, hinted_bytes) = parse(ParserOptions {
+            array_capacity_hint: Some(10_000),
+            ..Default::default()
+        });
+
+        assert_eq!(hinted, default);
+        assert_eq!(hinted.matches(',').count(), 9_999);
+        // Growing leaves the abandoned smaller buffers behind in the arena
+        assert!(hinted_bytes < default_bytes);
+    }
+}
@@ -1,44 +1,88 @@
+use std::collections::HashSet;
+
 use bumpalo::{Bump, collections::Vec};
 
 use crate::{
-    ast::{JsonProperty, JsonValue},
-    error::{ExpectedTokenError, IllegalReason},
-    illegal_number,
-    token::{Lexer, Token, TokenKind},
+    ast::{JsonProperty, JsonValue, borrowed_key},
+    decode::unescape_in,
+    error::{BorrowedError, ErrorContext, ExpectedTokenError, IllegalReason},
+    expected_token_err, illegal_number,
+    numbers::NumberHook,
+    options::ParserOptions,
+    source::{SourceMap, SourceRecorder},
+    token::{Lexer, Span, Token, TokenKind},
 };
 
-macro_rules! expected_token_err {
-    ($actual_token:expr, $row:expr, $column:expr, $expected_token:path) => {
-        return Err(ExpectedTokenError {
-            expected: vec![$expected_token],
-            actual: $actual_token.kind,
-            invalid_row: $row,
-            invalid_col: $column,
-        })
-    };
-    ($actual_token:expr, $row:expr, $column:expr, $( $variant:ident )|+) => {
-        return Err(ExpectedTokenError {
-            expected: vec![$(TokenKind::$variant),+],
-            actual: $actual_token.kind,
-            invalid_row: $row,
-            invalid_col: $column,
-        })
-    };
-}
-
 #[derive(Debug)]
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
+    options: ParserOptions,
     current_token: Token<'a>,
     peek_token: Token<'a>,
+    current_comment: Option<&'a str>,
+    peek_comment: Option<&'a str>,
+    sources: Option<SourceRecorder>,
+    tokens: Option<std::vec::Vec<Token<'a>>>,
+    raw_number_prefixes: std::vec::Vec<String>, // pointers whose numbers are kept as strings
+    number_hook: Option<NumberHook<'a>>,
+    values: usize,     // values parsed so far, checked against `options.max_values`
+    containers: usize, // containers entered so far, checked against `options.recursion_budget`
+    depth: usize,      // containers currently open, checked against `options.max_depth`
 }
 
 impl<'a> Parser<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::new_with_options(input, ParserOptions::default())
+    }
+
+    pub fn new_with_options(input: &'a str, options: ParserOptions) -> Self {
+        let lexer = match options
+            .strip_js_wrapper
+            .then(|| js_wrapper_bounds(input))
+            .flatten()
+        {
+            Some((start, end)) => Lexer::new_at(&input[..end], start, &options),
+            None => Lexer::new_with_options(input, &options),
+        };
+
+        Self::from_lexer(lexer, options)
+    }
+
+    /// Parses the JSON starting at the byte offset `start` of `input`, such as a body
+    /// following a header. Error rows and columns and source spans refer to positions in the
+    /// whole of `input`.
+    ///
+    /// Panics if `start` is not on a `char` boundary.
+    pub fn new_at(input: &'a str, start: usize) -> Self {
+        let options = ParserOptions::default();
+
+        Self::from_lexer(Lexer::new_at(input, start, &options), options)
+    }
+
+    /// Parses the JSON between the byte offsets `start` and `end` of `input`, treating
+    /// everything from `end` onwards as the end of input. Positions are reported relative to
+    /// the whole of `input`, as with [`Parser::new_at`].
+    ///
+    /// Panics if `start` or `end` is not on a `char` boundary, or `start` is after `end`.
+    pub fn new_range(input: &'a str, start: usize, end: usize) -> Self {
+        Self::new_at(&input[..end], start)
+    }
+
+    fn from_lexer(lexer: Lexer<'a>, options: ParserOptions) -> Self {
         let mut parser = Self {
-            lexer: Lexer::new(input),
+            lexer,
+            options,
             current_token: Token::default(),
             peek_token: Token::default(),
+            current_comment: None,
+            peek_comment: None,
+            sources: None,
+            tokens: None,
+            raw_number_prefixes: std::vec::Vec::new(),
+            number_hook: None,
+            values: 0,
+            containers: 0,
+            depth: 0,
         };
 
         parser.next_token();
@@ -46,19 +90,54 @@ impl<'a> Parser<'a> {
         parser
     }
 
+    /// Returns how many bytes of the input the lexer has consumed, for reporting progress.
+    ///
+    /// The parser reads one token ahead, so this is at the end of the token after the one
+    /// being parsed.
+    pub fn byte_offset(&self) -> usize {
+        self.lexer.byte_offset()
+    }
+
     fn next_token(&mut self) {
         self.current_token = self.peek_token;
+        self.current_comment = self.peek_comment;
         self.peek_token = self.lexer.next_token();
+        self.peek_comment = self.lexer.take_comment();
+
+        // Every bracket passes through here, so the depth is kept without touching the
+        // recursive container parsers
+        match self.current_token.kind {
+            TokenKind::LBrace | TokenKind::LBracket => self.depth += 1,
+            TokenKind::RBrace | TokenKind::RBracket => self.depth = self.depth.saturating_sub(1),
+            _ => (),
+        }
+
+        if let Some(tokens) = &mut self.tokens
+            && self.peek_token.kind != TokenKind::Eof
+        {
+            tokens.push(self.peek_token);
+        }
+    }
+
+    fn expect_peek(&mut self, expected: TokenKind) -> Result<(), BorrowedError<'a>> {
+        self.expect_peek_in(expected, None)
     }
 
-    fn expect_peek(&mut self, expected: TokenKind) -> Result<(), ExpectedTokenError> {
+    fn expect_peek_in(
+        &mut self,
+        expected: TokenKind,
+        context: Option<ErrorContext>,
+    ) -> Result<(), BorrowedError<'a>> {
         if self.peek_token.kind != expected {
-            expected_token_err!(
-                self.peek_token,
-                self.lexer.row,
-                self.peek_token.start_column,
-                expected
-            )
+            return Err(BorrowedError {
+                expected: vec![expected],
+                actual: self.peek_token.kind,
+                origin: self.peek_token.origin,
+                invalid_row: self.lexer.row,
+                invalid_col: self.peek_token.start_column,
+                span: self.peek_token.span,
+                context,
+            });
         }
 
         self.next_token();
@@ -66,120 +145,536 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    fn parse_string(&self, literal: &'a str) -> Result<JsonValue<'a>, ExpectedTokenError> {
-        Ok(JsonValue::String(literal))
+    /// Counts the value about to be parsed against [`ParserOptions::max_values`], and if it is
+    /// an object or array, against [`ParserOptions::recursion_budget`] and
+    /// [`ParserOptions::max_depth`].
+    fn count_value(&mut self) -> Result<(), BorrowedError<'a>> {
+        let container = matches!(
+            self.peek_token.kind,
+            TokenKind::LBrace | TokenKind::LBracket
+        );
+
+        self.values += 1;
+
+        if container {
+            self.containers += 1;
+        }
+
+        // The limits are checked here rather than on entering each container to keep the
+        // recursive frames small
+        match (
+            self.options.max_values,
+            self.options.recursion_budget,
+            self.options.max_depth,
+        ) {
+            (Some(max), _, _) if self.values > max => {
+                Err(self.limit_err(ErrorContext::TooManyValues(max)))
+            }
+            (_, Some(budget), _) if self.containers > budget => {
+                Err(self.limit_err(ErrorContext::RecursionBudgetExhausted(budget)))
+            }
+            (_, _, Some(max)) if container && self.depth >= max => {
+                Err(self.limit_err(ErrorContext::TooDeeplyNested(max)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Error for a limit that was exceeded at the next token.
+    fn limit_err(&self, context: ErrorContext) -> BorrowedError<'a> {
+        BorrowedError {
+            expected: vec![],
+            actual: self.peek_token.kind,
+            origin: self.peek_token.origin,
+            invalid_row: self.lexer.row,
+            invalid_col: self.peek_token.start_column,
+            span: self.peek_token.span,
+            context: Some(context),
+        }
+    }
+
+    /// Error for a container limit, reported at the container's opening bracket `open` at the
+    /// byte offset `start`.
+    fn container_limit_err(
+        &self,
+        start: usize,
+        open: TokenKind,
+        context: ErrorContext,
+    ) -> BorrowedError<'a> {
+        let (row, col) = self.lexer.position_of(start);
+        let span = Span {
+            start,
+            end: start + 1,
+        };
+
+        BorrowedError::new([], open)
+            .with_origin(&self.lexer.input()[start..start + 1])
+            .at(row, col)
+            .with_span(span)
+            .with_context(context)
+    }
+
+    /// Records the span from `start` up to the end of the current token for the value at the
+    /// current path, if sources are being collected.
+    fn record_source(&mut self, start: usize) {
+        if let Some(sources) = &mut self.sources {
+            sources.record(Span {
+                start,
+                end: self.current_token.span.end,
+            });
+        }
+    }
+
+    fn parse_string(
+        &self,
+        literal: &'a str,
+        bump: &'a Bump,
+    ) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        if self.options.coerce_numeric_strings
+            && let Some(n) = parse_numeric_string(literal)
+        {
+            return Ok(JsonValue::Number(n));
+        }
+
+        // The lexer has already rejected malformed escapes, so nothing is replaced
+        Ok(JsonValue::String(unescape_in(literal, bump)))
+    }
+
+    /// Whether the number at the current path is under one of the prefixes passed to
+    /// [`Parser::parse_with_raw_numbers`].
+    fn keeps_raw_number(&self) -> bool {
+        let Some(sources) = &self.sources else {
+            return false;
+        };
+
+        self.raw_number_prefixes.iter().any(|prefix| {
+            sources
+                .path()
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
     }
 
-    fn parse_number(&self, literal: &'a str) -> Result<JsonValue<'a>, ExpectedTokenError> {
-        let n = literal.parse::<f64>().map_err(|_| ExpectedTokenError {
+    fn parse_number(&self, literal: &'a str) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        let n = literal.parse::<f64>().map_err(|_| BorrowedError {
             expected: vec![TokenKind::Number],
             actual: illegal_number!(ParseFloatError),
+            origin: literal,
             invalid_row: self.lexer.row,
             invalid_col: self.peek_token.start_column,
+            span: self.peek_token.span,
+            context: None,
         })?;
 
+        if self.options.reject_precision_loss && loses_precision(literal, n) {
+            return Err(BorrowedError::new([TokenKind::Number], TokenKind::Number)
+                .with_origin(literal)
+                .at(self.lexer.row, self.peek_token.start_column)
+                .with_span(self.peek_token.span)
+                .with_context(ErrorContext::PrecisionLoss));
+        }
+
         Ok(JsonValue::Number(n))
     }
 
-    fn parse_value(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, ExpectedTokenError> {
+    /// Passes the number at the next token to the hook set by [`Parser::with_number_hook`],
+    /// failing if the hook rejects it.
+    fn parse_hooked_number(&mut self) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        let literal = self.peek_token.origin;
+        let pointer = self.sources.as_ref().map_or("", |sources| sources.path());
+
+        if let Some(hook) = &mut self.number_hook
+            && !hook.call(pointer, self.peek_token.span, literal)
+        {
+            return Err(BorrowedError::new([TokenKind::Number], TokenKind::Number)
+                .with_origin(literal)
+                .at(self.lexer.row, self.peek_token.start_column)
+                .with_span(self.peek_token.span));
+        }
+
+        self.parse_number(literal)
+    }
+
+    fn parse_value(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        let start = self.peek_token.span.start;
+
+        self.count_value()?;
+
+        // A single `?` keeps this frame small in debug builds, as it is repeated for every
+        // level of nesting
         let value = match &self.peek_token.kind {
-            TokenKind::String => self.parse_string(self.peek_token.origin)?,
-            TokenKind::Number => self.parse_number(self.peek_token.origin)?,
-            TokenKind::True => JsonValue::Boolean(true),
-            TokenKind::False => JsonValue::Boolean(false),
-            TokenKind::Null => JsonValue::Null,
-            TokenKind::LBrace => self.parse_object(bump)?,
-            TokenKind::LBracket => self.parse_array(bump)?,
+            TokenKind::LBrace => self.parse_object(bump),
+            TokenKind::LBracket => self.parse_array(bump),
+            _ => self.parse_scalar(bump),
+        }?;
+        self.next_token();
+        self.record_source(start);
+
+        Ok(value)
+    }
+
+    /// Parses any value other than an object or array.
+    ///
+    /// Kept out of `parse_value` so that its temporaries don't enlarge the stack frame that is
+    /// repeated for every level of nesting.
+    fn parse_scalar(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        match &self.peek_token.kind {
+            TokenKind::String => self.parse_string(self.peek_token.origin, bump),
+            TokenKind::Number if self.keeps_raw_number() => {
+                Ok(JsonValue::String(self.peek_token.origin))
+            }
+            TokenKind::Number if self.number_hook.is_some() => self.parse_hooked_number(),
+            TokenKind::Number => self.parse_number(self.peek_token.origin),
+            TokenKind::True => Ok(JsonValue::Boolean(true)),
+            TokenKind::False => Ok(JsonValue::Boolean(false)),
+            TokenKind::Null => Ok(JsonValue::Null),
+            // Diagnosed here rather than in `parse_array` to keep its stack frame small
+            TokenKind::Comma if self.current_token.kind == TokenKind::LBracket => {
+                expected_token_err!(
+                    self.peek_token,
+                    self.lexer.row,
+                    self.peek_token.start_column,
+                    String | Number | Null | LBrace | LBracket | True | False | RBracket; LeadingComma
+                )
+            }
             _ => {
                 expected_token_err!(
                     self.peek_token,
                     self.lexer.row,
                     self.peek_token.start_column,
-                    String | Number | Null | LBrace | LBracket | True | False
+                    String | Number | Null | LBrace | LBracket | True | False; Value
                 )
             }
-        };
-        self.next_token();
+        }
+    }
 
-        Ok(value)
+    /// Consumes an object key and the `:` after it, returning the decoded key and its leading
+    /// comment.
+    fn parse_key(
+        &mut self,
+        bump: &'a Bump,
+    ) -> Result<(&'a str, Option<&'a str>), BorrowedError<'a>> {
+        self.expect_key()?;
+
+        let key = unescape_in(self.current_token.origin, bump);
+
+        self.validate_key(key)?;
+
+        let leading_comment = self.current_comment;
+
+        self.expect_peek_in(TokenKind::Colon, Some(ErrorContext::ObjectColon))?;
+
+        Ok((key, leading_comment))
+    }
+
+    /// Consumes an object key and the `:` after it like [`Parser::parse_key`], only decoding
+    /// the key when [`ParserOptions::validate_keys`] needs to see it.
+    fn skip_key(&mut self, bump: &'a Bump) -> Result<(), BorrowedError<'a>> {
+        self.expect_key()?;
+
+        if self.options.validate_keys.is_some() {
+            self.validate_key(unescape_in(self.current_token.origin, bump))?;
+        }
+
+        self.expect_peek_in(TokenKind::Colon, Some(ErrorContext::ObjectColon))
+    }
+
+    /// Consumes the string of an object key.
+    fn expect_key(&mut self) -> Result<(), BorrowedError<'a>> {
+        if self.peek_token.kind == TokenKind::Comma && self.current_token.kind == TokenKind::LBrace
+        {
+            expected_token_err!(
+                self.peek_token,
+                self.lexer.row,
+                self.peek_token.start_column,
+                String | RBrace; LeadingComma
+            )
+        }
+
+        self.expect_peek_in(TokenKind::String, Some(ErrorContext::ObjectKey))
     }
 
-    fn parse_property(&mut self, bump: &'a Bump) -> Result<JsonProperty<'a>, ExpectedTokenError> {
-        self.expect_peek(TokenKind::String)?;
+    /// Checks `key`, the decoded form of the key just consumed, against
+    /// [`ParserOptions::validate_keys`].
+    fn validate_key(&self, key: &str) -> Result<(), BorrowedError<'a>> {
+        if let Some(validate) = self.options.validate_keys
+            && !validate(key)
+        {
+            return Err(BorrowedError::new([TokenKind::String], TokenKind::String)
+                .with_origin(self.current_token.origin)
+                .at(self.lexer.row, self.current_token.start_column)
+                .with_span(self.current_token.span)
+                .with_context(ErrorContext::InvalidKey));
+        }
+
+        Ok(())
+    }
 
-        let key = self.current_token.origin;
+    fn parse_property(&mut self, bump: &'a Bump) -> Result<JsonProperty<'a>, BorrowedError<'a>> {
+        let (key, leading_comment) = self.parse_key(bump)?;
 
-        self.expect_peek(TokenKind::Colon)?;
+        let path_len = self.sources.as_mut().map(|sources| sources.push_key(key));
 
         let value = self.parse_value(bump)?;
 
-        Ok(JsonProperty::from((key, value)))
+        if let (Some(sources), Some(len)) = (&mut self.sources, path_len) {
+            sources.pop(len);
+        }
+
+        Ok(JsonProperty {
+            key: borrowed_key(key),
+            value,
+            leading_comment,
+        })
     }
 
-    fn parse_array(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, ExpectedTokenError> {
-        self.expect_peek(TokenKind::LBracket)?;
+    fn parse_array(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        // Only called with the opening bracket as the next token
+        self.next_token();
 
         if self.peek_token.kind == TokenKind::RBracket {
             return Ok(JsonValue::Array(Vec::new_in(bump)));
         }
 
-        let mut items = Vec::with_capacity_in(8, bump);
+        let start = self.current_token.span.start;
+        let mut items = Vec::with_capacity_in(self.options.array_capacity_hint.unwrap_or(8), bump);
 
         loop {
+            let path_len = self
+                .sources
+                .as_mut()
+                .map(|sources| sources.push_index(items.len()));
+
             let value = self.parse_value(bump)?;
             items.push(value);
 
-            match &self.peek_token.kind {
-                TokenKind::Comma => self.next_token(),
-                TokenKind::RBracket => break,
-                _ => {
-                    expected_token_err!(
-                        self.peek_token,
-                        self.lexer.row,
-                        self.peek_token.start_column,
-                        Comma | RBracket
-                    )
-                }
+            if let (Some(sources), Some(len)) = (&mut self.sources, path_len) {
+                sources.pop(len);
+            }
+
+            if !self.array_separator(start, items.len())? {
+                break;
             }
         }
 
         Ok(JsonValue::Array(items))
     }
 
-    fn parse_object(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, ExpectedTokenError> {
-        self.expect_peek(TokenKind::LBrace)?;
+    fn parse_object(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        // Only called with the opening bracket as the next token
+        self.next_token();
 
         if self.peek_token.kind == TokenKind::RBrace {
             return Ok(JsonValue::Object(Vec::new_in(bump)));
         }
 
+        let start = self.current_token.span.start;
         let mut items = Vec::with_capacity_in(8, bump);
 
         loop {
             let item = self.parse_property(bump)?;
             items.push(item);
 
-            match &self.peek_token.kind {
-                TokenKind::Comma => self.next_token(),
-                TokenKind::RBrace => break,
-                _ => {
-                    expected_token_err!(
-                        self.peek_token,
-                        self.lexer.row,
-                        self.peek_token.start_column,
-                        Comma | RBrace
-                    )
+            if !self.object_separator(start, items.len())? {
+                break;
+            }
+        }
+
+        Ok(JsonValue::Object(items))
+    }
+
+    /// Returns `true` if the `,` just consumed is directly followed by `close` and trailing
+    /// commas are allowed.
+    fn at_trailing_comma(&self, close: TokenKind) -> bool {
+        self.options.allow_trailing_commas && self.peek_token.kind == close
+    }
+
+    /// Consumes the `,` after an array element, returning `false` at the closing `]`. `start`
+    /// is the offset of the array's `[` and `len` the number of elements so far, checked
+    /// against [`ParserOptions::max_array_elements`] when another element follows.
+    ///
+    /// Kept out of `parse_array` so that the error paths don't enlarge its stack frame, which
+    /// is repeated for every level of nesting.
+    fn array_separator(&mut self, start: usize, len: usize) -> Result<bool, BorrowedError<'a>> {
+        match &self.peek_token.kind {
+            TokenKind::Comma => {
+                self.next_token();
+
+                if self.at_trailing_comma(TokenKind::RBracket) {
+                    return Ok(false);
                 }
+
+                match self.options.max_array_elements {
+                    Some(max) if len >= max => Err(self.container_limit_err(
+                        start,
+                        TokenKind::LBracket,
+                        ErrorContext::TooManyArrayElements(max),
+                    )),
+                    _ => Ok(true),
+                }
+            }
+            TokenKind::RBracket => Ok(false),
+            kind if kind.starts_value() => {
+                expected_token_err!(
+                    self.peek_token,
+                    self.lexer.row,
+                    self.peek_token.start_column,
+                    Comma | RBracket; MissingArrayComma
+                )
+            }
+            _ => {
+                expected_token_err!(
+                    self.peek_token,
+                    self.lexer.row,
+                    self.peek_token.start_column,
+                    Comma | RBracket
+                )
+            }
+        }
+    }
+
+    /// Consumes the `,` after an object member, returning `false` at the closing `}`, and
+    /// checks [`ParserOptions::max_object_members`] like [`Parser::array_separator`].
+    fn object_separator(&mut self, start: usize, len: usize) -> Result<bool, BorrowedError<'a>> {
+        match &self.peek_token.kind {
+            TokenKind::Comma => {
+                self.next_token();
+
+                if self.at_trailing_comma(TokenKind::RBrace) {
+                    return Ok(false);
+                }
+
+                match self.options.max_object_members {
+                    Some(max) if len >= max => Err(self.container_limit_err(
+                        start,
+                        TokenKind::LBrace,
+                        ErrorContext::TooManyObjectMembers(max),
+                    )),
+                    _ => Ok(true),
+                }
+            }
+            TokenKind::RBrace => Ok(false),
+            // A key where the comma should be
+            TokenKind::String => {
+                expected_token_err!(
+                    self.peek_token,
+                    self.lexer.row,
+                    self.peek_token.start_column,
+                    Comma | RBrace; MissingObjectComma
+                )
+            }
+            _ => {
+                expected_token_err!(
+                    self.peek_token,
+                    self.lexer.row,
+                    self.peek_token.start_column,
+                    Comma | RBrace
+                )
+            }
+        }
+    }
+
+    /// Checks the next value like [`Parser::parse_value`] without building it, leaving it as
+    /// the current token.
+    fn skip_value(&mut self, bump: &'a Bump) -> Result<(), BorrowedError<'a>> {
+        self.count_value()?;
+
+        match self.peek_token.kind {
+            TokenKind::LBrace => {
+                self.next_token();
+
+                let start = self.current_token.span.start;
+
+                if self.peek_token.kind != TokenKind::RBrace {
+                    for len in 1.. {
+                        self.skip_key(bump)?;
+                        self.skip_value(bump)?;
+
+                        if !self.object_separator(start, len)? {
+                            break;
+                        }
+                    }
+                }
+            }
+            TokenKind::LBracket => {
+                self.next_token();
+
+                let start = self.current_token.span.start;
+
+                if self.peek_token.kind != TokenKind::RBracket {
+                    for len in 1.. {
+                        self.skip_value(bump)?;
+
+                        if !self.array_separator(start, len)? {
+                            break;
+                        }
+                    }
+                }
+            }
+            // The lexer has already checked the string, so decoding it into the arena would
+            // only waste memory
+            TokenKind::String => (),
+            _ => {
+                self.parse_scalar(bump)?;
             }
         }
 
+        self.next_token();
+
+        Ok(())
+    }
+
+    /// Parses a root object, building only the values of the keys in `keep` and skipping the
+    /// rest.
+    fn parse_filtered_root(
+        &mut self,
+        keep: &HashSet<&str>,
+        bump: &'a Bump,
+    ) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        self.count_value()?;
+        self.expect_peek(TokenKind::LBrace)?;
+
+        let start = self.current_token.span.start;
+        let mut items = Vec::with_capacity_in(8, bump);
+
+        if self.peek_token.kind != TokenKind::RBrace {
+            // Skipped members count towards `max_object_members` too
+            for len in 1.. {
+                let (key, leading_comment) = self.parse_key(bump)?;
+
+                if keep.contains(key) {
+                    let value = self.parse_value(bump)?;
+
+                    items.push(JsonProperty {
+                        key: borrowed_key(key),
+                        value,
+                        leading_comment,
+                    });
+                } else {
+                    self.skip_value(bump)?;
+                }
+
+                if !self.object_separator(start, len)? {
+                    break;
+                }
+            }
+        }
+
+        self.next_token();
+        self.expect_peek(TokenKind::Eof)?;
+
         Ok(JsonValue::Object(items))
     }
 
-    fn parse_root_object(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, ExpectedTokenError> {
+    fn parse_root_object(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        let start = self.peek_token.span.start;
+
+        self.count_value()?;
+
         let result = self.parse_object(bump)?;
 
         self.next_token();
+        self.record_source(start);
 
         if !matches!(
             (&self.current_token.kind, &self.peek_token.kind),
@@ -196,10 +691,15 @@ impl<'a> Parser<'a> {
         Ok(result)
     }
 
-    fn parse_root_array(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, ExpectedTokenError> {
+    fn parse_root_array(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+        let start = self.peek_token.span.start;
+
+        self.count_value()?;
+
         let result = self.parse_array(bump)?;
 
         self.next_token();
+        self.record_source(start);
 
         if !matches!(
             (&self.current_token.kind, &self.peek_token.kind),
@@ -217,6 +717,125 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse(mut self, bump: &'a Bump) -> Result<JsonValue<'a>, ExpectedTokenError> {
+        Ok(self.parse_document(bump)?)
+    }
+
+    /// Prints every token of `input` with its row, column and span, followed by the parsed
+    /// value or the error, for debugging inputs that don't parse as expected.
+    #[cfg(feature = "debug-tools")]
+    pub fn inspect(input: &str) {
+        let mut lexer = Lexer::new(input);
+
+        println!("tokens:");
+
+        loop {
+            let token = lexer.next_token();
+
+            println!(
+                "  {}:{} {:?} {:?} {}..{}",
+                lexer.row,
+                token.start_column,
+                token.kind,
+                token.origin,
+                token.span.start,
+                token.span.end
+            );
+
+            if token.kind == TokenKind::Eof {
+                break;
+            }
+        }
+
+        let bump = Bump::new();
+
+        match Parser::new(input).parse(&bump) {
+            Ok(value) => println!("value:\n{value:#?}"),
+            Err(err) => println!("error:\n{err}"),
+        }
+    }
+
+    /// Parses the first value in the input and stops, returning it with the byte offset just
+    /// past its last token, i.e. how much of the input it consumed.
+    ///
+    /// Unlike [`Parser::parse`], anything after the value, such as another document, is left
+    /// unread rather than being an error. Trailing whitespace isn't counted as consumed.
+    pub fn parse_prefix(
+        mut self,
+        bump: &'a Bump,
+    ) -> Result<(JsonValue<'a>, usize), ExpectedTokenError> {
+        let value = self.parse_value(bump)?;
+
+        Ok((value, self.current_token.span.end))
+    }
+
+    /// Parses the input like [`Parser::parse`], additionally returning a [`SourceMap`] with
+    /// the originating slice of every value.
+    pub fn parse_with_sources(
+        mut self,
+        bump: &'a Bump,
+    ) -> Result<(JsonValue<'a>, SourceMap<'a>), ExpectedTokenError> {
+        self.sources = Some(SourceRecorder::default());
+
+        let value = self.parse_document(bump)?;
+        let sources = self.sources.take().unwrap_or_default();
+
+        Ok((value, sources.finish(self.lexer.input())))
+    }
+
+    /// Parses the input like [`Parser::parse`], except that numbers at or below any of the
+    /// JSON Pointers in `prefixes` are kept as [`JsonValue::String`]s holding their exact
+    /// source text, such as IDs too large to be represented by an `f64`.
+    pub fn parse_with_raw_numbers(
+        mut self,
+        prefixes: &[&str],
+        bump: &'a Bump,
+    ) -> Result<JsonValue<'a>, ExpectedTokenError> {
+        self.sources = Some(SourceRecorder::path_only());
+        self.raw_number_prefixes = prefixes.iter().map(|prefix| prefix.to_string()).collect();
+
+        Ok(self.parse_document(bump)?)
+    }
+
+    /// Calls `hook` with the JSON Pointer, span and source text of every number as it is
+    /// parsed, stopping with an error as soon as it returns `false`.
+    pub(crate) fn with_number_hook(mut self, hook: NumberHook<'a>) -> Self {
+        self.sources = Some(SourceRecorder::path_only());
+        self.number_hook = Some(hook);
+
+        self
+    }
+
+    /// Parses a top-level array one element at a time, so each element can be processed
+    /// before the next one is parsed.
+    ///
+    /// Yields an error for input that isn't an array, a malformed element or anything after
+    /// the closing `]`, and stops after the first error.
+    pub fn array_elements(self, bump: &'a Bump) -> ArrayElements<'a> {
+        ArrayElements {
+            parser: self,
+            bump,
+            started: false,
+            done: false,
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Parses the input like [`Parser::parse`], additionally returning every token in the
+    /// order it was read, excluding the final [`TokenKind::Eof`].
+    pub fn parse_with_tokens(
+        mut self,
+        bump: &'a Bump,
+    ) -> Result<(JsonValue<'a>, std::vec::Vec<Token<'a>>), ExpectedTokenError> {
+        // The first token has already been read by the constructor
+        self.tokens = Some(std::vec![self.peek_token]);
+
+        let value = self.parse_document(bump)?;
+
+        Ok((value, self.tokens.take().unwrap_or_default()))
+    }
+
+    fn parse_document(&mut self, bump: &'a Bump) -> Result<JsonValue<'a>, BorrowedError<'a>> {
         match self.peek_token.kind {
             TokenKind::LBrace => self.parse_root_object(bump),
             TokenKind::LBracket => self.parse_root_array(bump),
@@ -224,6 +843,20 @@ impl<'a> Parser<'a> {
             | TokenKind::Number
             | TokenKind::True
             | TokenKind::False
+            | TokenKind::Null
+                if self.options.require_container_root =>
+            {
+                expected_token_err!(
+                    self.peek_token,
+                    self.lexer.row,
+                    self.peek_token.start_column,
+                    LBrace | LBracket; ContainerRoot
+                )
+            }
+            TokenKind::String
+            | TokenKind::Number
+            | TokenKind::True
+            | TokenKind::False
             | TokenKind::Null => {
                 let result = self.parse_value(bump)?;
 
@@ -235,17 +868,272 @@ impl<'a> Parser<'a> {
                 self.peek_token,
                 self.lexer.row,
                 self.peek_token.start_column,
-                String | Number | Null | LBrace | LBracket | True | False
+                String | Number | Null | LBrace | LBracket | True | False; Value
             ),
         }
     }
 }
 
+/// Iterator over the elements of a top-level array, returned by [`Parser::array_elements`].
+#[derive(Debug)]
+pub struct ArrayElements<'a> {
+    parser: Parser<'a>,
+    bump: &'a Bump,
+    started: bool,
+    done: bool,
+    start: usize, // offset of the opening `[`
+    len: usize,   // elements parsed so far
+}
+
+impl<'a> ArrayElements<'a> {
+    fn next_element(&mut self) -> Result<Option<JsonValue<'a>>, BorrowedError<'a>> {
+        let parser = &mut self.parser;
+
+        if !self.started {
+            self.started = true;
+
+            parser.count_value()?;
+            parser.expect_peek(TokenKind::LBracket)?;
+            self.start = parser.current_token.span.start;
+
+            if parser.peek_token.kind == TokenKind::RBracket {
+                parser.next_token();
+                parser.expect_peek(TokenKind::Eof)?;
+
+                return Ok(None);
+            }
+        } else if !parser.array_separator(self.start, self.len)? {
+            parser.next_token();
+            parser.expect_peek(TokenKind::Eof)?;
+
+            return Ok(None);
+        }
+
+        self.len += 1;
+
+        parser.parse_value(self.bump).map(Some)
+    }
+}
+
+impl<'a> Iterator for ArrayElements<'a> {
+    type Item = Result<JsonValue<'a>, ExpectedTokenError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_element() {
+            Ok(Some(value)) => Some(Ok(value)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+/// Parses a string's contents as a number if the whole of it is a valid JSON number.
+fn parse_numeric_string(literal: &str) -> Option<f64> {
+    let token = Lexer::new(literal).next_token();
+
+    if token.kind != TokenKind::Number
+        || token.span
+            != (Span {
+                start: 0,
+                end: literal.len(),
+            })
+    {
+        return None;
+    }
+
+    literal.parse().ok()
+}
+
+/// Finds the JSON inside a JSONP callback such as `cb({...});` or a JavaScript assignment
+/// such as `var x = {...};`, returning its start and end byte offsets.
+fn js_wrapper_bounds(input: &str) -> Option<(usize, usize)> {
+    let is_ident = |ch: char| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '$' | '.');
+
+    let trimmed = input.trim_start();
+    let declared = ["var ", "let ", "const "]
+        .iter()
+        .find_map(|keyword| trimmed.strip_prefix(keyword))
+        .unwrap_or(trimmed)
+        .trim_start();
+
+    let name_len = declared.find(|ch| !is_ident(ch))?;
+
+    if name_len == 0 || declared.starts_with(|ch: char| ch.is_ascii_digit()) {
+        return None;
+    }
+
+    let rest = declared[name_len..].trim_start();
+    let body = input.trim_end();
+    let body = body.strip_suffix(';').unwrap_or(body).trim_end();
+
+    let (inner, body) = match rest.strip_prefix('(') {
+        Some(inner) => (inner, body.strip_suffix(')')?),
+        None => (rest.strip_prefix('=')?, body),
+    };
+    let start = input.len() - inner.len();
+
+    (start <= body.len()).then_some((start, body.len()))
+}
+
+/// Whether `literal` is an integer that `n`, the `f64` parsed from it, doesn't represent
+/// exactly.
+pub(crate) fn loses_precision(literal: &str, n: f64) -> bool {
+    // Every integer below 2^53 is exact
+    const EXACT_BELOW: f64 = 9_007_199_254_740_992.0;
+
+    let digits = literal.strip_prefix('-').unwrap_or(literal);
+
+    if n.abs() < EXACT_BELOW || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return false;
+    }
+
+    // Unlike `to_string`, which gives the shortest digits that round-trip, formatting with a
+    // precision writes out the exact value of the `f64`
+    !n.is_finite() || format!("{:.0}", n.abs()) != digits.trim_start_matches('0')
+}
+
+/// Parses an object document, keeping only the top-level keys in `keep`. The values of other
+/// keys are still checked but never allocated.
+pub fn parse_filtered<'a>(
+    input: &'a str,
+    keep: &HashSet<&str>,
+    bump: &'a Bump,
+) -> Result<JsonValue<'a>, ExpectedTokenError> {
+    Ok(Parser::new(input).parse_filtered_root(keep, bump)?)
+}
+
+/// Parses `input` like [`Parser::parse`], returning a [`BorrowedError`] whose origin is a
+/// slice of `input`, so the error can be kept for as long as the input without holding a
+/// copy of the offending text.
+pub fn parse_borrowed_err<'a>(
+    input: &'a str,
+    bump: &'a Bump,
+) -> Result<JsonValue<'a>, BorrowedError<'a>> {
+    Parser::new(input).parse_document(bump)
+}
+
+/// Parses a top-level array of numbers straight into a `Vec<f64>` without building any
+/// [`JsonValue`] nodes.
+///
+/// As soon as anything other than a number, `,` or `]` shows up the general parser takes over
+/// so that malformed input reports the same error as [`Parser::parse`]. Valid JSON that isn't
+/// an array of numbers errors at the first element that isn't a number.
+pub fn parse_number_array(input: &str) -> Result<std::vec::Vec<f64>, ExpectedTokenError> {
+    let mut lexer = Lexer::new(input);
+    let mut numbers = std::vec::Vec::new();
+
+    let mut token = lexer.next_token();
+    let mut fast_path = token.kind == TokenKind::LBracket;
+
+    if fast_path {
+        token = lexer.next_token();
+
+        if token.kind == TokenKind::RBracket {
+            token = lexer.next_token();
+        } else {
+            loop {
+                match (token.kind, token.origin.parse::<f64>()) {
+                    (TokenKind::Number, Ok(n)) => numbers.push(n),
+                    _ => {
+                        fast_path = false;
+                        break;
+                    }
+                }
+
+                token = lexer.next_token();
+
+                match token.kind {
+                    TokenKind::Comma => token = lexer.next_token(),
+                    TokenKind::RBracket => {
+                        token = lexer.next_token();
+                        break;
+                    }
+                    _ => {
+                        fast_path = false;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    if fast_path && token.kind == TokenKind::Eof {
+        return Ok(numbers);
+    }
+
+    let bump = Bump::new();
+
+    Parser::new(input).parse(&bump)?;
+
+    // The input is valid JSON, so `token` is the first thing that isn't part of a number array
+    Err(ExpectedTokenError {
+        expected: vec![TokenKind::Number],
+        actual: token.kind,
+        origin: token.origin.to_string(),
+        invalid_row: lexer.row,
+        invalid_col: token.start_column,
+        span: token.span,
+        context: None,
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        fs,
+    };
 
     use super::*;
+    use crate::options::DEFAULT_MAX_DEPTH;
+
+    /// Counts the allocations made on each thread, so a test can check that a path doesn't
+    /// allocate without being disturbed by tests running on other threads.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATIONS.with(Cell::get);
+        let result = f();
+
+        (result, ALLOCATIONS.with(Cell::get) - before)
+    }
+
+    #[cfg(feature = "debug-tools")]
+    #[test]
+    fn inspect_valid_and_invalid() {
+        Parser::inspect(r#"{"a": [1, "two", null]}"#);
+        Parser::inspect("[1, @, ]\n{");
+    }
 
     #[test]
     fn parse_top_level_object() {
@@ -319,6 +1207,34 @@ mod tests {
         insta::assert_debug_snapshot!(parser.parse(&bump));
     }
 
+    #[test]
+    fn parse_decodes_escapes() {
+        let json = r#"{"line\tkey": ["line1\nline2", "say \"hi\" \\ a\/b", "\u00e9t\u00E9 \ud83d\ude00", "plain", "\b\f\r"]}"#;
+
+        let bump = Bump::new();
+        let root = Parser::new(json).parse(&bump).unwrap();
+
+        assert_eq!(root.as_object().unwrap()[0].key, "line\tkey");
+        assert_eq!(
+            root.pointer("/line\tkey").unwrap().as_str_vec(),
+            Some(vec![
+                "line1\nline2",
+                "say \"hi\" \\ a/b",
+                "été 😀",
+                "plain",
+                "\u{8}\u{c}\r"
+            ])
+        );
+        assert_eq!(
+            root.to_json_string().unwrap(),
+            r#"{"line\tkey":["line1\nline2","say \"hi\" \\ a/b","été 😀","plain","\b\f\r"]}"#
+        );
+        assert_eq!(
+            Parser::new(&root.to_json_string().unwrap()).parse(&bump),
+            Ok(root)
+        );
+    }
+
     #[test]
     fn parse_valid_decimal() {
         let json = r#"
@@ -343,6 +1259,570 @@ mod tests {
         insta::assert_debug_snapshot!(parser.parse(&bump));
     }
 
+    #[test]
+    fn parse_exponent_case_matrix() {
+        let bump = Bump::new();
+
+        let parse = |input| Parser::new(input).parse(&bump);
+
+        for input in ["1E10", "1e10", "1E+10", "1e+10", "10000000000"] {
+            assert_eq!(parse(input), Ok(JsonValue::Number(1e10)), "{input}");
+        }
+
+        for input in ["1E-10", "1e-10", "0.0000000001"] {
+            assert_eq!(parse(input), Ok(JsonValue::Number(1e-10)), "{input}");
+        }
+
+        for input in ["0e1", "0E1", "0e+1", "0E-1"] {
+            assert_eq!(parse(input), Ok(JsonValue::Number(0.0)), "{input}");
+        }
+
+        let illegal = |input| parse(input).unwrap_err().actual;
+
+        for input in ["1.e5", "1.E5", "0.e1", "0.E1"] {
+            assert_eq!(illegal(input), illegal_number!(MissingFraction), "{input}");
+        }
+
+        for input in ["0e", "0E", "1e", "1E", "1e+", "1E+", "1e-", "1E-"] {
+            assert_eq!(illegal(input), illegal_number!(MissingExponent), "{input}");
+        }
+    }
+
+    #[test]
+    fn parse_number_array_matches_parser() {
+        let json = format!(
+            "[{}]",
+            (0..10_000)
+                .map(|i| format!("{}e-3", i * 7 - 5000))
+                .collect::<std::vec::Vec<_>>()
+                .join(", ")
+        );
+
+        let bump = Bump::new();
+        let Ok(JsonValue::Array(expected)) = Parser::new(&json).parse(&bump) else {
+            panic!("expected an array");
+        };
+
+        let numbers = parse_number_array(&json).unwrap();
+
+        assert_eq!(numbers.len(), 10_000);
+        assert!(
+            numbers
+                .iter()
+                .zip(expected.iter())
+                .all(|(n, value)| *value == JsonValue::Number(*n))
+        );
+        assert_eq!(parse_number_array(" [ ] "), Ok(vec![]));
+    }
+
+    #[test]
+    fn parse_number_array_fallback() {
+        let bump = Bump::new();
+
+        let err = parse_number_array(r#"[1, 2, "three"]"#).unwrap_err();
+
+        assert_eq!(err.expected, vec![TokenKind::Number]);
+        assert_eq!(err.actual, TokenKind::String);
+        assert_eq!(err.invalid_col, 8);
+
+        assert_eq!(
+            parse_number_array("[1, 2,]"),
+            Parser::new("[1, 2,]").parse(&bump).map(|_| vec![])
+        );
+        assert_eq!(
+            parse_number_array("[1, 02]"),
+            Parser::new("[1, 02]").parse(&bump).map(|_| vec![])
+        );
+        assert_eq!(
+            parse_number_array("{}").unwrap_err().actual,
+            TokenKind::LBrace
+        );
+    }
+
+    #[test]
+    fn parse_at_offset() {
+        let buffer = "POST /ingest HTTP/1.1\r\nContent-Type: application/json\r\n\r\n{\n  \"id\": 7,\n  \"tags\": [\"a\", ]\n}";
+        let start = buffer.find("\r\n\r\n").unwrap() + 4;
+
+        let bump = Bump::new();
+        let err = Parser::new_at(buffer, start).parse(&bump).unwrap_err();
+
+        assert_eq!(err.actual, TokenKind::RBracket);
+        assert_eq!((err.invalid_row, err.invalid_col), (6, 17));
+
+        let valid = "HEADER\n[1, 2]";
+        let (value, sources) = Parser::new_at(valid, 7).parse_with_sources(&bump).unwrap();
+
+        assert_eq!(value, Parser::new("[1, 2]").parse(&bump).unwrap());
+        assert_eq!(sources.span("/1"), Some(Span { start: 11, end: 12 }));
+    }
+
+    #[test]
+    fn parse_filtered_skips_other_keys() {
+        let input = r#"{"id": 7, "big": {"rows": [[1, 2], [3, 4]], "meta": {"a": "b"}}, "name": "x", "tags": ["a", "b", "c"], "nested": [{"deep": [true]}]}"#;
+        let keep = HashSet::from(["id", "name"]);
+
+        let bump = Bump::new();
+        let value = parse_filtered(input, &keep, &bump).unwrap();
+
+        let expected_bump = Bump::new();
+        let expected = Parser::new(r#"{"id": 7, "name": "x"}"#)
+            .parse(&expected_bump)
+            .unwrap();
+
+        // SAFETY: nothing allocates in either arena while the chunks are being inspected
+        let used = |bump: &Bump| {
+            unsafe { bump.iter_allocated_chunks_raw() }
+                .map(|(_, len)| len)
+                .sum::<usize>()
+        };
+
+        assert_eq!(value, expected);
+        assert_eq!(used(&bump), used(&expected_bump));
+
+        let full_bump = Bump::new();
+        Parser::new(input).parse(&full_bump).unwrap();
+
+        assert!(used(&bump) < used(&full_bump));
+
+        // Skipped strings and keys aren't decoded, so escapes cost nothing either
+        let skipped = |text: &str| {
+            let input = format!(r#"{{"id": 1, "big": {{"{text}": ["{text}"]}}}}"#);
+            let bump = Bump::new();
+
+            parse_filtered(&input, &keep, &bump).unwrap();
+            bump.allocated_bytes()
+        };
+
+        assert_eq!(skipped(&"\\n".repeat(50_000)), skipped(&"n".repeat(50_000)));
+
+        // Skipped values must still be valid
+        for invalid in [
+            r#"{"id": 1, "big": [1, ]}"#,
+            r#"{"id": 1, "big": {"a" 1}}"#,
+            r#"{"big": [01]}"#,
+            r#"{"id": 1} 2"#,
+            "[1]",
+        ] {
+            assert!(parse_filtered(invalid, &keep, &bump).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_prefix_consumed() {
+        let bump = Bump::new();
+
+        for input in [r#"{"a": [1, 2]}"#, "{\"a\": [1, 2]}  \n", "42\t"] {
+            let (value, consumed) = Parser::new(input).parse_prefix(&bump).unwrap();
+
+            assert_eq!(consumed, input.trim_end().len());
+            assert_eq!(Ok(value), Parser::new(input).parse(&bump));
+        }
+
+        let stream = r#"{"id": 1} {"id": 2}"#;
+        let (first, consumed) = Parser::new(stream).parse_prefix(&bump).unwrap();
+        let (second, end) = Parser::new_at(stream, consumed)
+            .parse_prefix(&bump)
+            .unwrap();
+
+        assert_eq!(consumed, 9);
+        assert_eq!(end, stream.len());
+        assert_eq!(first.get_present("id"), Some(&JsonValue::Number(1.0)));
+        assert_eq!(second.get_present("id"), Some(&JsonValue::Number(2.0)));
+        assert!(Parser::new(stream).parse(&bump).is_err());
+    }
+
+    #[test]
+    fn parse_range() {
+        let log = "12:00:01 payload=<{\"id\": 7, \"ok\": true}> status=<[1, 2]>";
+        let start = log.find('<').unwrap() + 1;
+        let end = log.find('>').unwrap();
+
+        let bump = Bump::new();
+        let (value, sources) = Parser::new_range(log, start, end)
+            .parse_with_sources(&bump)
+            .unwrap();
+
+        assert_eq!(
+            value,
+            Parser::new(r#"{"id": 7, "ok": true}"#)
+                .parse(&bump)
+                .unwrap()
+        );
+        assert_eq!(sources.span("/id"), Some(Span { start: 25, end: 26 }));
+
+        let err = Parser::new_range(log, start, end - 1)
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(err.actual, TokenKind::Eof);
+    }
+
+    #[test]
+    fn parse_borrowed_err_slices_input() {
+        let bump = Bump::new();
+        let input = std::string::String::from("{\"a\": [1, tru],\n \"b\": \"x\ty\"}");
+
+        for (json, origin, start) in [
+            (input.as_str(), "tru", 10),
+            ("[1, \"x\ty\"]", "x\ty", 5),
+            ("[1", "", 2),
+        ] {
+            let err = parse_borrowed_err(json, &bump).unwrap_err();
+
+            assert_eq!(err.origin, origin);
+            assert_eq!(err.origin.as_ptr(), json[start..].as_ptr());
+            assert_eq!(
+                err.clone().into_owned(),
+                Parser::new(json).parse(&bump).unwrap_err()
+            );
+            assert_eq!(
+                err.to_string(),
+                Parser::new(json).parse(&bump).unwrap_err().to_string()
+            );
+        }
+
+        assert!(parse_borrowed_err("[1]", &bump).is_ok());
+
+        // Too deep an array errors with nothing expected, so only the origin could allocate
+        let json = "[".repeat(DEFAULT_MAX_DEPTH + 1);
+        let bump = Bump::with_capacity(1 << 20);
+
+        let (borrowed, borrowed_allocations) =
+            allocations(|| parse_borrowed_err(&json, &bump).unwrap_err());
+        let (owned, owned_allocations) =
+            allocations(|| Parser::new(&json).parse(&bump).unwrap_err());
+
+        assert_eq!(
+            borrowed.context,
+            Some(ErrorContext::TooDeeplyNested(DEFAULT_MAX_DEPTH))
+        );
+        assert_eq!(borrowed_allocations, 0);
+        assert_eq!(owned_allocations, 1);
+        assert_eq!(borrowed.to_string(), owned.to_string());
+    }
+
+    #[test]
+    fn parse_with_raw_numbers_under_prefix() {
+        let json = r#"{"ids": [9007199254740993, 1.50], "idsx": 2, "meta": {"ids": 3, "n": [4]}}"#;
+
+        let bump = Bump::new();
+        let value = Parser::new(json)
+            .parse_with_raw_numbers(&["/ids", "/meta/n"], &bump)
+            .unwrap();
+
+        assert_eq!(
+            value,
+            Parser::new(
+                r#"{"ids": ["9007199254740993", "1.50"], "idsx": 2, "meta": {"ids": 3, "n": ["4"]}}"#
+            )
+            .parse(&bump)
+            .unwrap()
+        );
+        assert_eq!(
+            Parser::new("[1, 2.0]")
+                .parse_with_raw_numbers(&[""], &bump)
+                .unwrap(),
+            Parser::new(r#"["1", "2.0"]"#).parse(&bump).unwrap()
+        );
+        assert_eq!(
+            Parser::new(json)
+                .parse_with_raw_numbers(&[], &bump)
+                .unwrap(),
+            Parser::new(json).parse(&bump).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_with_tokens_covers_input() {
+        let json = "{\n  \"a\": [1, true, null],\n  \"b\": {\"c\": \"d\"}\n}";
+
+        let bump = Bump::new();
+        let (value, tokens) = Parser::new(json).parse_with_tokens(&bump).unwrap();
+
+        assert_eq!(value, Parser::new(json).parse(&bump).unwrap());
+        assert_eq!(tokens.len(), 19);
+
+        // Only whitespace separates consecutive tokens
+        let mut end = 0;
+
+        for token in &tokens {
+            assert!(json[end..token.span.start].trim().is_empty());
+
+            let source = &json[token.span.start..token.span.end];
+
+            match token.kind {
+                TokenKind::String => assert_eq!(source, format!("\"{}\"", token.origin)),
+                _ => assert_eq!(source, token.origin),
+            }
+
+            end = token.span.end;
+        }
+
+        assert_eq!(end, json.len());
+    }
+
+    #[test]
+    fn byte_offset_increases() {
+        let json = r#"{"name": "John", "scores": [95, 88, 76], "nested": {"ok": true}}"#;
+
+        let mut parser = Parser::new(json);
+        let mut offsets = std::vec![parser.byte_offset()];
+
+        while parser.peek_token.kind != TokenKind::Eof {
+            parser.next_token();
+            offsets.push(parser.byte_offset());
+        }
+
+        assert_eq!(offsets[0], 1);
+        assert!(offsets.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(offsets.last(), Some(&json.len()));
+    }
+
+    #[test]
+    fn array_elements_one_at_a_time() {
+        let bump = Bump::new();
+        let json = r#"[1, {"a": [true]}, "three", []]"#;
+
+        let elements = Parser::new(json)
+            .array_elements(&bump)
+            .collect::<Result<std::vec::Vec<_>, _>>()
+            .unwrap();
+
+        let Ok(JsonValue::Array(expected)) = Parser::new(json).parse(&bump) else {
+            panic!("expected an array");
+        };
+
+        assert_eq!(elements, expected.as_slice());
+        assert_eq!(Parser::new(" [ ] ").array_elements(&bump).count(), 0);
+    }
+
+    #[test]
+    fn array_elements_stop_at_error() {
+        let bump = Bump::new();
+        let json = r#"[1, {"a": 2}, [3,, 4], 5]"#;
+
+        let mut elements = Parser::new(json).array_elements(&bump);
+
+        assert_eq!(elements.next(), Some(Ok(JsonValue::Number(1.0))));
+        assert!(matches!(elements.next(), Some(Ok(JsonValue::Object(_)))));
+        assert_eq!(
+            elements.next(),
+            Some(Err(Parser::new(json).parse(&bump).unwrap_err()))
+        );
+        assert_eq!(elements.next(), None);
+
+        let mut trailing = Parser::new("[1] 2").array_elements(&bump);
+
+        assert_eq!(trailing.next(), Some(Ok(JsonValue::Number(1.0))));
+        assert!(matches!(trailing.next(), Some(Err(e)) if e.actual == TokenKind::Number));
+
+        let not_array = Parser::new("{}").array_elements(&bump).next();
+
+        assert!(matches!(not_array, Some(Err(e)) if e.expected == [TokenKind::LBracket]));
+    }
+
+    #[test]
+    fn parse_missing_comma() {
+        let bump = Bump::new();
+
+        let object = Parser::new(r#"{"a":1 "b":2}"#).parse(&bump).unwrap_err();
+
+        assert_eq!(object.context, Some(ErrorContext::MissingObjectComma));
+        assert_eq!(
+            object.to_string(),
+            "expected ',' between object members at row 1 column 8 but got 'STRING' instead"
+        );
+
+        let array = Parser::new("[1 2]").parse(&bump).unwrap_err();
+
+        assert_eq!(array.context, Some(ErrorContext::MissingArrayComma));
+        assert_eq!(
+            array.to_string(),
+            "expected ',' between array elements at row 1 column 4 but got 'NUMBER' instead"
+        );
+
+        // Tokens that can't start the next member keep the general message
+        let colon = Parser::new(r#"{"a":1 :}"#).parse(&bump).unwrap_err();
+
+        assert_eq!(colon.context, None);
+        assert_eq!(colon.expected, [TokenKind::Comma, TokenKind::RBrace]);
+    }
+
+    #[test]
+    fn parse_empty_roots_after_trivia() {
+        let bump = Bump::new();
+        let jsonc = ParserOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+
+        for (input, options) in [
+            ("{}", ParserOptions::default()),
+            ("[]", ParserOptions::default()),
+            ("\u{FEFF}{}", ParserOptions::default()),
+            ("\u{FEFF} \r\n\t[]", ParserOptions::default()),
+            ("// config\n{}", jsonc),
+            ("\u{FEFF}/* a */ /* b */\n{ }", jsonc),
+        ] {
+            let value = Parser::new_with_options(input, options)
+                .parse(&bump)
+                .unwrap_or_else(|e| panic!("{input:?}: {e}"));
+
+            assert!(
+                value
+                    .as_object()
+                    .is_some_and(|properties| properties.is_empty())
+                    || value.as_array().is_some_and(|items| items.is_empty()),
+                "{input:?} parsed to {value:?}"
+            );
+        }
+
+        // The byte order mark isn't counted as a column
+        let err = Parser::new("\u{FEFF}[1,]").parse(&bump).unwrap_err();
+
+        assert_eq!((err.invalid_row, err.invalid_col), (1, 4));
+        assert_eq!(err.span, Span { start: 6, end: 7 });
+        assert!(Parser::new("\u{FEFF}").parse(&bump).is_err());
+        assert!(Parser::new("[\u{FEFF}]").parse(&bump).is_err());
+    }
+
+    #[test]
+    fn parse_leading_comma() {
+        let bump = Bump::new();
+
+        let array = Parser::new("[,1]").parse(&bump).unwrap_err();
+
+        assert_eq!(array.context, Some(ErrorContext::LeadingComma));
+        assert_eq!(
+            array.to_string(),
+            "unexpected ',' before the first element at row 1 column 2"
+        );
+
+        let object = Parser::new(r#"{,"a":1}"#).parse(&bump).unwrap_err();
+
+        assert_eq!(object.context, Some(ErrorContext::LeadingComma));
+        assert_eq!(object.expected, [TokenKind::String, TokenKind::RBrace]);
+        assert_eq!(
+            object.to_string(),
+            "unexpected ',' before the first element at row 1 column 2"
+        );
+
+        // A doubled comma later on is still reported as a missing value
+        let doubled = Parser::new("[1,,2]").parse(&bump).unwrap_err();
+
+        assert_eq!(doubled.context, Some(ErrorContext::Value));
+    }
+
+    #[test]
+    fn parse_missing_colon() {
+        let bump = Bump::new();
+        let err = Parser::new(r#"{"a" 1}"#).parse(&bump).unwrap_err();
+
+        assert_eq!(err.context, Some(ErrorContext::ObjectColon));
+        assert_eq!(err.invalid_col, 6);
+        assert_eq!(
+            err.to_string(),
+            "expected ':' after object key at row 1 column 6 but got 'NUMBER' instead"
+        );
+    }
+
+    #[test]
+    fn parse_missing_value() {
+        let bump = Bump::new();
+        let err = Parser::new(r#"{"a": }"#).parse(&bump).unwrap_err();
+
+        assert_eq!(err.actual, TokenKind::RBrace);
+        assert_eq!(err.context, Some(ErrorContext::Value));
+        assert_eq!(err.invalid_col, 7);
+        assert_eq!(
+            err.to_string(),
+            "expected a JSON value at row 1 column 7 but got '}' instead"
+        );
+    }
+
+    #[test]
+    fn parse_delimiter_as_root() {
+        let bump = Bump::new();
+
+        for (input, actual) in [
+            (":", TokenKind::Colon),
+            (",", TokenKind::Comma),
+            ("}", TokenKind::RBrace),
+            ("\n  ]", TokenKind::RBracket),
+        ] {
+            let err = Parser::new(input).parse(&bump).unwrap_err();
+
+            assert_eq!(err.actual, actual);
+            assert_eq!(err.context, Some(ErrorContext::Value));
+            assert!(err.expected_value());
+            assert_eq!(err.span.end - err.span.start, 1);
+        }
+
+        assert_eq!(
+            Parser::new(":").parse(&bump).unwrap_err().to_string(),
+            "expected a JSON value at row 1 column 1 but got ':' instead"
+        );
+        assert_eq!(
+            Parser::new("\n  ]").parse(&bump).unwrap_err().to_string(),
+            "expected a JSON value at row 2 column 3 but got ']' instead"
+        );
+    }
+
+    #[test]
+    fn parse_non_string_key() {
+        let bump = Bump::new();
+        let err = Parser::new(r#"{1: 2}"#).parse(&bump).unwrap_err();
+
+        assert_eq!(err.expected, vec![TokenKind::String]);
+        assert_eq!(err.context, Some(ErrorContext::ObjectKey));
+        assert_eq!(
+            err.to_string(),
+            "expected object key (string) at row 1 column 2 but got 'NUMBER' instead"
+        );
+    }
+
+    #[test]
+    fn parse_leading_comments() {
+        let json = r#"
+{
+    // The port to listen on
+    "port": 8080,
+    /* Hostname,
+       without scheme */
+    "host": "localhost",
+    "debug": false // not a leading comment
+}
+"#;
+
+        let bump = Bump::new();
+        let options = ParserOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+
+        let Ok(JsonValue::Object(properties)) =
+            Parser::new_with_options(json, options).parse(&bump)
+        else {
+            panic!("expected an object");
+        };
+
+        let comments = properties
+            .iter()
+            .map(|property| (property.key_str(), property.leading_comment))
+            .collect::<std::vec::Vec<_>>();
+
+        assert_eq!(
+            comments,
+            [
+                ("port", Some("// The port to listen on")),
+                ("host", Some("/* Hostname,\n       without scheme */")),
+                ("debug", None),
+            ]
+        );
+        assert!(Parser::new(json).parse(&bump).is_err());
+    }
+
     #[test]
     fn parse_test_data() {
         insta::glob!("../../test_data", "**/*.json", |path| {
@@ -0,0 +1,483 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use bumpalo::{Bump, collections::Vec};
+
+use crate::ast::{JsonProperty, JsonValue};
+
+type Segments<'p> = std::vec::Vec<Cow<'p, str>>;
+
+/// Why [`JsonValue::with`] couldn't replace the value at a pointer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PointerError {
+    /// The pointer is neither empty nor starts with a `/`.
+    Invalid,
+    /// The pointer doesn't resolve to an existing value.
+    NotFound,
+}
+
+impl std::fmt::Display for PointerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PointerError::Invalid => write!(f, "JSON Pointer must be empty or start with '/'"),
+            PointerError::NotFound => write!(f, "JSON Pointer doesn't resolve to a value"),
+        }
+    }
+}
+
+impl std::error::Error for PointerError {}
+
+/// Splits an RFC 6901 pointer into its unescaped reference tokens.
+///
+/// Returns `None` when the pointer is neither empty nor starts with a `/`.
+fn parse_pointer(pointer: &str) -> Option<Segments<'_>> {
+    if pointer.is_empty() {
+        return Some(Segments::new());
+    }
+
+    let segments = pointer.strip_prefix('/')?.split('/').map(|segment| {
+        if segment.contains('~') {
+            Cow::Owned(segment.replace("~1", "/").replace("~0", "~"))
+        } else {
+            Cow::Borrowed(segment)
+        }
+    });
+
+    Some(segments.collect())
+}
+
+/// Parses an array index reference token, rejecting leading zeros and `-`.
+fn parse_index(segment: &str) -> Option<usize> {
+    match segment.as_bytes() {
+        [b'0'] => Some(0),
+        [b'1'..=b'9', rest @ ..] if rest.iter().all(u8::is_ascii_digit) => segment.parse().ok(),
+        _ => None,
+    }
+}
+
+impl<'a> JsonValue<'a> {
+    fn child(&self, segment: &str) -> Option<&JsonValue<'a>> {
+        match self {
+            JsonValue::Object(properties) => properties
+                .iter()
+                .rev()
+                .find(|property| property.key == segment)
+                .map(|property| &property.value),
+            JsonValue::Array(json_values) => json_values.get(parse_index(segment)?),
+            _ => None,
+        }
+    }
+
+    /// Looks up a value by RFC 6901 JSON Pointer, e.g. `/foo/0/bar`.
+    ///
+    /// Returns `None` for missing keys, out of range indices or when descending into a scalar.
+    /// When an object repeats a key the last occurrence wins, matching [`JsonValue::flattened`].
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonValue<'a>> {
+        parse_pointer(pointer)?
+            .iter()
+            .try_fold(self, |value, segment| value.child(segment))
+    }
+
+    /// Returns how deeply the value at `pointer` is nested, with the root at depth `0`, or
+    /// `None` when the pointer does not resolve.
+    pub fn depth_at(&self, pointer: &str) -> Option<usize> {
+        let segments = parse_pointer(pointer)?;
+
+        segments
+            .iter()
+            .try_fold(self, |value, segment| value.child(segment))
+            .map(|_| segments.len())
+    }
+
+    /// Returns the value at `pointer`, first creating whatever is missing along the way with
+    /// the final slot set to `null`.
+    ///
+    /// A missing level becomes an array when the next segment is an index or `-` (append),
+    /// and an object otherwise. Like RFC 6902's `add`, an array only grows by appending, with
+    /// `-` or an index equal to its length.
+    /// Returns `None` for an invalid pointer, one that runs through a scalar other than `null`
+    /// or through an array with a non-index segment, or one with an index past the end of an
+    /// array, leaving existing data intact.
+    pub fn ensure_path(&mut self, pointer: &str, bump: &'a Bump) -> Option<&mut JsonValue<'a>> {
+        let segments = parse_pointer(pointer)?;
+
+        // Checked up front so that nothing is created along a path that turns out to fail
+        if !self.can_ensure(&segments) {
+            return None;
+        }
+
+        self.ensure_segments(&segments, bump)
+    }
+
+    /// Whether [`JsonValue::ensure_path`] can create whatever is missing along `segments`.
+    fn can_ensure(&self, segments: &[Cow<'_, str>]) -> bool {
+        let Some((segment, rest)) = segments.split_first() else {
+            return true;
+        };
+
+        match self {
+            // Everything from here on is created, and a new array can only take index 0
+            JsonValue::Null => segments
+                .iter()
+                .all(|segment| parse_index(segment).is_none_or(|index| index == 0)),
+            JsonValue::Object(_) => self
+                .child(segment)
+                .unwrap_or(&JsonValue::Null)
+                .can_ensure(rest),
+            JsonValue::Array(json_values) => {
+                let index = match &**segment {
+                    "-" => Some(json_values.len()),
+                    _ => parse_index(segment),
+                };
+
+                match index {
+                    Some(index) if index < json_values.len() => json_values[index].can_ensure(rest),
+                    Some(index) if index == json_values.len() => JsonValue::Null.can_ensure(rest),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn ensure_segments(
+        &mut self,
+        segments: &[Cow<'_, str>],
+        bump: &'a Bump,
+    ) -> Option<&mut JsonValue<'a>> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return Some(self);
+        };
+
+        if *self == JsonValue::Null {
+            *self = if segment == "-" || parse_index(segment).is_some() {
+                JsonValue::Array(bumpalo::vec![in bump])
+            } else {
+                JsonValue::Object(bumpalo::vec![in bump])
+            };
+        }
+
+        let child = match self {
+            JsonValue::Object(properties) => {
+                let position = match properties
+                    .iter()
+                    .rposition(|property| property.key == *segment)
+                {
+                    Some(position) => position,
+                    None => {
+                        let key = bump.alloc_str(segment);
+
+                        properties.push(JsonProperty::from((&*key, JsonValue::Null)));
+                        properties.len() - 1
+                    }
+                };
+
+                &mut properties[position].value
+            }
+            JsonValue::Array(json_values) => {
+                let index = match &**segment {
+                    "-" => json_values.len(),
+                    _ => parse_index(segment)?,
+                };
+
+                if index == json_values.len() {
+                    json_values.push(JsonValue::Null);
+                }
+
+                json_values.get_mut(index)?
+            }
+            _ => return None,
+        };
+
+        child.ensure_segments(rest, bump)
+    }
+
+    /// Returns a copy of the value with the value at `pointer` replaced by `value`, leaving
+    /// `self` untouched.
+    ///
+    /// The objects and arrays along the path are rebuilt in `bump` and everything else is
+    /// cloned. Unlike [`JsonValue::ensure_path`] nothing is created, so the pointer must
+    /// resolve to an existing value.
+    pub fn with(
+        &self,
+        pointer: &str,
+        value: JsonValue<'a>,
+        bump: &'a Bump,
+    ) -> Result<JsonValue<'a>, PointerError> {
+        let segments = parse_pointer(pointer).ok_or(PointerError::Invalid)?;
+
+        self.with_segments(&segments, value, bump)
+    }
+
+    fn with_segments(
+        &self,
+        segments: &[Cow<'_, str>],
+        value: JsonValue<'a>,
+        bump: &'a Bump,
+    ) -> Result<JsonValue<'a>, PointerError> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return Ok(value);
+        };
+
+        match self {
+            JsonValue::Object(properties) => {
+                let position = properties
+                    .iter()
+                    .rposition(|property| property.key == *segment)
+                    .ok_or(PointerError::NotFound)?;
+                let replaced = properties[position]
+                    .value
+                    .with_segments(rest, value, bump)?;
+
+                let mut items = Vec::from_iter_in(properties.iter().cloned(), bump);
+
+                items[position].value = replaced;
+
+                Ok(JsonValue::Object(items))
+            }
+            JsonValue::Array(json_values) => {
+                let index = parse_index(segment)
+                    .filter(|&index| index < json_values.len())
+                    .ok_or(PointerError::NotFound)?;
+                let replaced = json_values[index].with_segments(rest, value, bump)?;
+
+                let mut items = Vec::from_iter_in(json_values.iter().cloned(), bump);
+
+                items[index] = replaced;
+
+                Ok(JsonValue::Array(items))
+            }
+            _ => Err(PointerError::NotFound),
+        }
+    }
+
+    /// Resolves several pointers at once, sharing the traversal of common prefixes.
+    ///
+    /// Pointers that do not resolve are left out of the result.
+    pub fn select(&self, pointers: &[&str]) -> BTreeMap<String, &JsonValue<'a>> {
+        let mut paths = pointers
+            .iter()
+            .filter_map(|pointer| Some((*pointer, parse_pointer(pointer)?)))
+            .collect::<std::vec::Vec<_>>();
+
+        paths.sort_by(|a, b| a.1.cmp(&b.1));
+
+        let mut selected = BTreeMap::new();
+
+        self.select_sorted(&paths, 0, &mut selected);
+
+        selected
+    }
+
+    /// Every entry of `paths` shares its first `depth` segments, all of which lead to `self`.
+    fn select_sorted<'s>(
+        &'s self,
+        paths: &[(&str, Segments<'_>)],
+        depth: usize,
+        selected: &mut BTreeMap<String, &'s JsonValue<'a>>,
+    ) {
+        let mut rest = paths;
+
+        while let Some((pointer, segments)) = rest.first() {
+            let Some(segment) = segments.get(depth) else {
+                selected.insert(pointer.to_string(), self);
+                rest = &rest[1..];
+                continue;
+            };
+
+            let group_len = rest
+                .iter()
+                .take_while(|(_, other)| other.get(depth) == Some(segment))
+                .count();
+            let (group, tail) = rest.split_at(group_len);
+
+            if let Some(child) = self.child(segment) {
+                child.select_sorted(group, depth + 1, selected);
+            }
+
+            rest = tail;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::parser::Parser;
+
+    const DOCUMENT: &str = r#"
+{
+    "name": "John",
+    "address": { "city": "New York", "street": { "number": 95 } },
+    "scores": [95, 88, 76]
+}
+"#;
+
+    #[test]
+    fn pointer_lookup() {
+        let bump = Bump::new();
+        let root = Parser::new(DOCUMENT).parse(&bump).unwrap();
+
+        assert_eq!(root.pointer(""), Some(&root));
+        assert_eq!(root.pointer("/name"), Some(&JsonValue::String("John")));
+        assert_eq!(
+            root.pointer("/address/street/number"),
+            Some(&JsonValue::Number(95.0))
+        );
+        assert_eq!(root.pointer("/scores/2"), Some(&JsonValue::Number(76.0)));
+        assert_eq!(root.pointer("/scores/3"), None);
+        assert_eq!(root.pointer("/scores/01"), None);
+        assert_eq!(root.pointer("/name/first"), None);
+        assert_eq!(root.pointer("name"), None);
+    }
+
+    #[test]
+    fn pointer_escaped_keys() {
+        let bump = Bump::new();
+        let root = Parser::new(r#"{"a/b": {"m~n": [1]}, "~1": 2, "\u002f": 3}"#)
+            .parse(&bump)
+            .unwrap();
+
+        assert_eq!(root.pointer("/a~1b/m~0n/0"), Some(&JsonValue::Number(1.0)));
+        assert_eq!(root.pointer("/~01"), Some(&JsonValue::Number(2.0)));
+        assert_eq!(root.pointer("/~1"), Some(&JsonValue::Number(3.0)));
+    }
+
+    #[test]
+    fn depth_at_pointer() {
+        let bump = Bump::new();
+        let root = Parser::new(DOCUMENT).parse(&bump).unwrap();
+
+        assert_eq!(root.depth_at(""), Some(0));
+        assert_eq!(root.depth_at("/scores"), Some(1));
+        assert_eq!(root.depth_at("/address/street/number"), Some(3));
+        assert_eq!(root.depth_at("/address/street/missing"), None);
+        assert_eq!(root.depth_at("/scores/7"), None);
+    }
+
+    #[test]
+    fn with_replaces_copy() {
+        let bump = Bump::new();
+        let root = Parser::new(DOCUMENT).parse(&bump).unwrap();
+        let original = root.clone();
+
+        let renumbered = root
+            .with("/address/street/number", JsonValue::Number(12.0), &bump)
+            .unwrap();
+        let rescored = renumbered
+            .with("/scores/1", JsonValue::Null, &bump)
+            .unwrap();
+
+        assert_eq!(root, original);
+        assert_eq!(
+            renumbered.pointer("/address/street/number"),
+            Some(&JsonValue::Number(12.0))
+        );
+        assert_eq!(
+            renumbered.pointer("/scores/1"),
+            Some(&JsonValue::Number(88.0))
+        );
+        assert_eq!(rescored.pointer("/scores/1"), Some(&JsonValue::Null));
+        assert_eq!(
+            rescored.pointer("/address/city"),
+            root.pointer("/address/city")
+        );
+
+        assert_eq!(
+            root.with("", JsonValue::Boolean(true), &bump),
+            Ok(JsonValue::Boolean(true))
+        );
+        assert_eq!(
+            root.with("scores", JsonValue::Null, &bump),
+            Err(PointerError::Invalid)
+        );
+
+        for missing in ["/missing", "/scores/3", "/scores/-", "/name/first"] {
+            assert_eq!(
+                root.with(missing, JsonValue::Null, &bump),
+                Err(PointerError::NotFound),
+                "{missing}"
+            );
+        }
+    }
+
+    #[test]
+    fn ensure_path_from_empty_object() {
+        let bump = Bump::new();
+        let mut root = Parser::new("{}").parse(&bump).unwrap();
+
+        root.ensure_path("/config/servers/0", &bump).unwrap();
+        *root.ensure_path("/config/servers/1/host", &bump).unwrap() = JsonValue::String("b");
+        *root.ensure_path("/config/servers/-/host", &bump).unwrap() = JsonValue::String("c");
+        root.ensure_path("/config/a~1b", &bump).unwrap();
+
+        assert_eq!(
+            root,
+            Parser::new(
+                r#"{"config": {"servers": [null, {"host": "b"}, {"host": "c"}], "a/b": null}}"#
+            )
+            .parse(&bump)
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn ensure_path_in_existing_document() {
+        let bump = Bump::new();
+        let mut root = Parser::new(DOCUMENT).parse(&bump).unwrap();
+
+        *root.ensure_path("/address/street/name", &bump).unwrap() = JsonValue::String("Main");
+        *root.ensure_path("/scores/0", &bump).unwrap() = JsonValue::Number(100.0);
+
+        assert_eq!(
+            root.pointer("/address/street/number"),
+            Some(&JsonValue::Number(95.0))
+        );
+        assert_eq!(
+            root.pointer("/address/street/name"),
+            Some(&JsonValue::String("Main"))
+        );
+        assert_eq!(root.pointer("/scores/0"), Some(&JsonValue::Number(100.0)));
+
+        let before = root.clone();
+
+        assert_eq!(root.ensure_path("", &bump).cloned(), Some(before.clone()));
+        assert_eq!(root.ensure_path("/name/first", &bump), None);
+        assert_eq!(root.ensure_path("/scores/x", &bump), None);
+        assert_eq!(root.ensure_path("name", &bump), None);
+
+        // Indices past the end aren't padded, however large, and nothing is created on the way
+        for pointer in [
+            "/scores/4",
+            "/scores/18446744073709551615",
+            "/scores/1000000000000",
+            "/new/list/1",
+            "/new/list/0/2",
+        ] {
+            assert_eq!(root.ensure_path(pointer, &bump), None, "{pointer}");
+        }
+
+        assert_eq!(root, before);
+    }
+
+    #[test]
+    fn select_shares_prefixes_and_skips_misses() {
+        let bump = Bump::new();
+        let root = Parser::new(DOCUMENT).parse(&bump).unwrap();
+
+        let selected = root.select(&[
+            "/address/street/number",
+            "/address/missing",
+            "/address/city",
+        ]);
+
+        assert_eq!(
+            selected,
+            BTreeMap::from([
+                ("/address/city".into(), &JsonValue::String("New York")),
+                ("/address/street/number".into(), &JsonValue::Number(95.0)),
+            ])
+        );
+    }
+}
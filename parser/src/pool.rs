@@ -0,0 +1,161 @@
+use std::{mem::ManuallyDrop, ops::Deref, sync::Mutex};
+
+use bumpalo::Bump;
+
+use crate::{ast::JsonValue, error::ExpectedTokenError, options::ParserOptions, parser::Parser};
+
+/// Recycles [`Bump`] arenas between parses, so that a service parsing many small documents
+/// reuses the memory of earlier ones instead of allocating fresh arenas every time.
+///
+/// Can be shared between threads. Arenas are reset when they are returned, keeping their
+/// largest chunk for the next parse.
+#[derive(Debug, Default)]
+pub struct ParserPool {
+    arenas: Mutex<Vec<Bump>>,
+    max_idle: usize,
+}
+
+impl ParserPool {
+    /// Creates an empty pool that keeps at most `max_idle` arenas for reuse. Arenas returned
+    /// while the pool is full are freed.
+    pub fn new(max_idle: usize) -> Self {
+        Self {
+            arenas: Mutex::new(Vec::with_capacity(max_idle)),
+            max_idle,
+        }
+    }
+
+    /// Takes an idle arena from the pool, or creates one if there are none. The arena goes
+    /// back to the pool when the returned guard is dropped.
+    pub fn arena(&self) -> PooledArena<'_> {
+        let bump = self.lock().pop().unwrap_or_default();
+
+        PooledArena {
+            pool: self,
+            bump: ManuallyDrop::new(bump),
+        }
+    }
+
+    /// Returns the number of arenas waiting to be reused.
+    pub fn idle(&self) -> usize {
+        self.lock().len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Bump>> {
+        // The arenas are only ever pushed and popped, so they are still usable after a
+        // panic elsewhere while the lock was held
+        self.arenas
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// An arena borrowed from a [`ParserPool`], returned to it on drop.
+///
+/// Values parsed with [`PooledArena::parse`] borrow the guard, so they can't outlive it.
+#[derive(Debug)]
+pub struct PooledArena<'p> {
+    pool: &'p ParserPool,
+    bump: ManuallyDrop<Bump>,
+}
+
+impl PooledArena<'_> {
+    pub fn parse<'a>(&'a self, input: &'a str) -> Result<JsonValue<'a>, ExpectedTokenError> {
+        Parser::new(input).parse(&self.bump)
+    }
+
+    pub fn parse_with_options<'a>(
+        &'a self,
+        input: &'a str,
+        options: ParserOptions,
+    ) -> Result<JsonValue<'a>, ExpectedTokenError> {
+        Parser::new_with_options(input, options).parse(&self.bump)
+    }
+}
+
+impl Deref for PooledArena<'_> {
+    type Target = Bump;
+
+    fn deref(&self) -> &Bump {
+        &self.bump
+    }
+}
+
+impl Drop for PooledArena<'_> {
+    fn drop(&mut self) {
+        // SAFETY: `bump` is never used again after being taken here.
+        let mut bump = unsafe { ManuallyDrop::take(&mut self.bump) };
+
+        // Reset before taking the lock, to hold it as briefly as possible
+        bump.reset();
+
+        let mut arenas = self.pool.lock();
+
+        if arenas.len() < self.pool.max_idle {
+            arenas.push(bump);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn reuses_arenas() {
+        let pool = ParserPool::new(2);
+
+        for id in 0..100 {
+            let input = format!(r#"{{"id": {id}, "tags": ["a", "b"], "nested": {{"n": null}}}}"#);
+            let arena = pool.arena();
+            let value = arena.parse(&input).unwrap();
+
+            assert_eq!(value.pointer("/id"), Some(&JsonValue::Number(id as f64)));
+            assert_eq!(value.pointer("/tags/1"), Some(&JsonValue::String("b")));
+        }
+
+        // Only one arena was ever needed at a time, and it was reset before being kept
+        assert_eq!(pool.idle(), 1);
+
+        let arena = pool.arena();
+
+        // A new `Bump` allocates nothing until first used, so memory here was kept from
+        // earlier parses
+        assert_eq!(pool.idle(), 0);
+        assert!(arena.allocated_bytes() > 0);
+        assert_eq!(Bump::new().allocated_bytes(), 0);
+        assert!(arena.parse("[1,]").is_err());
+    }
+
+    #[test]
+    fn keeps_at_most_max_idle() {
+        let pool = ParserPool::new(2);
+
+        let arenas: Vec<_> = (0..4).map(|_| pool.arena()).collect();
+
+        drop(arenas);
+
+        assert_eq!(pool.idle(), 2);
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let pool = ParserPool::new(4);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..50 {
+                        let arena = pool.arena();
+
+                        assert!(arena.parse(r#"[{"a": 1}, [2, 3]]"#).is_ok());
+                    }
+                });
+            }
+        });
+
+        assert!((1..=4).contains(&pool.idle()));
+    }
+}
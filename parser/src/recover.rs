@@ -0,0 +1,533 @@
+use std::collections::BTreeSet;
+
+use bumpalo::{Bump, collections::Vec};
+
+use crate::{
+    ast::{JsonProperty, JsonValue, borrowed_key},
+    decode::unescape_in,
+    error::{ErrorContext, ExpectedTokenError, IllegalReason},
+    illegal_number,
+    options::DEFAULT_MAX_DEPTH,
+    parser::loses_precision,
+    source::SourceRecorder,
+    token::{Lexer, Token, TokenKind},
+};
+
+const VALUE_START: [TokenKind; 7] = [
+    TokenKind::String,
+    TokenKind::Number,
+    TokenKind::Null,
+    TokenKind::LBrace,
+    TokenKind::LBracket,
+    TokenKind::True,
+    TokenKind::False,
+];
+
+/// Something about a document that doesn't stop it from parsing but may still need attention.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// An integer that can't be represented exactly by an `f64` and was rounded.
+    PrecisionLoss {
+        /// JSON Pointer to the number.
+        pointer: String,
+        /// Source text of the number.
+        text: String,
+    },
+}
+
+/// The result of [`parse_recovering`].
+#[derive(Debug)]
+pub struct Recovered<'a> {
+    /// The parsed value, with `null` in place of anything that couldn't be parsed.
+    pub value: JsonValue<'a>,
+    /// Every error found, in the order they appear in the input.
+    pub errors: std::vec::Vec<ExpectedTokenError>,
+    /// Problems that didn't stop a value from being parsed, in the order they appear in the
+    /// input.
+    pub warnings: std::vec::Vec<Warning>,
+    /// JSON Pointers to the values that don't faithfully reflect the input because of an
+    /// error: a `null` placeholder, or the innermost object or array where something was
+    /// skipped. Anything after the root value is reported without marking the root.
+    pub recovered: BTreeSet<String>,
+}
+
+impl Recovered<'_> {
+    /// Returns `true` if the value at `pointer` is, or is inside, a value that was altered by
+    /// recovery and so shouldn't be trusted.
+    pub fn is_recovered(&self, pointer: &str) -> bool {
+        self.recovered.iter().any(|recovered| {
+            pointer
+                .strip_prefix(recovered.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
+/// Parses `input`, carrying on past errors instead of stopping at the first one so that all
+/// of the problems in a document can be reported at once.
+///
+/// After an error the parser resumes at the next `,` or closing bracket, and any value it
+/// couldn't parse is replaced with `null` and listed in [`Recovered::recovered`]. Every token
+/// after the end of the root value is reported separately. Errors are the same as
+/// [`Parser::parse`] would report for the first of them.
+///
+/// Objects and arrays nested deeper than [`DEFAULT_MAX_DEPTH`] are reported and skipped
+/// whole, becoming `null`, so that no input can overflow the stack.
+///
+/// [`Parser::parse`]: crate::parser::Parser::parse
+pub fn parse_recovering<'a>(input: &'a str, bump: &'a Bump) -> Recovered<'a> {
+    let mut recoverer = Recoverer {
+        lexer: Lexer::new(input),
+        peek: Token::default(),
+        path: SourceRecorder::path_only(),
+        errors: std::vec::Vec::new(),
+        warnings: std::vec::Vec::new(),
+        recovered: BTreeSet::new(),
+        depth: 0,
+    };
+
+    recoverer.advance();
+
+    let value = recoverer.value(bump);
+
+    // Every token left over is reported, not just the first
+    while recoverer.peek.kind != TokenKind::Eof {
+        let error = recoverer.token_error(&[TokenKind::Eof], None);
+
+        recoverer.errors.push(error);
+        recoverer.advance();
+    }
+
+    Recovered {
+        value,
+        errors: recoverer.errors,
+        warnings: recoverer.warnings,
+        recovered: recoverer.recovered,
+    }
+}
+
+struct Recoverer<'a> {
+    lexer: Lexer<'a>,
+    peek: Token<'a>, // the next token, not yet consumed
+    path: SourceRecorder,
+    errors: std::vec::Vec<ExpectedTokenError>,
+    warnings: std::vec::Vec<Warning>,
+    recovered: BTreeSet<String>,
+    depth: usize, // objects and arrays currently open
+}
+
+impl<'a> Recoverer<'a> {
+    /// Consumes the next token and returns it.
+    fn advance(&mut self) -> Token<'a> {
+        std::mem::replace(&mut self.peek, self.lexer.next_token())
+    }
+
+    /// Builds an error for the next token.
+    fn token_error(
+        &self,
+        expected: &[TokenKind],
+        context: Option<ErrorContext>,
+    ) -> ExpectedTokenError {
+        ExpectedTokenError {
+            context,
+            ..ExpectedTokenError::new(expected, self.peek.kind)
+                .with_origin(self.peek.origin)
+                .at(self.lexer.row, self.peek.start_column)
+                .with_span(self.peek.span)
+        }
+    }
+
+    /// Records an error for the next token, marking the value at the current path as
+    /// recovered.
+    fn error(&mut self, expected: &[TokenKind], context: Option<ErrorContext>) {
+        let error = self.token_error(expected, context);
+
+        self.errors.push(error);
+        self.recovered.insert(self.path.path().to_owned());
+    }
+
+    /// Consumes the next token unless it is one that an enclosing container can resume at.
+    fn skip_unless_resumable(&mut self) {
+        if !matches!(
+            self.peek.kind,
+            TokenKind::Comma | TokenKind::RBrace | TokenKind::RBracket | TokenKind::Eof
+        ) {
+            self.advance();
+        }
+    }
+
+    fn value(&mut self, bump: &'a Bump) -> JsonValue<'a> {
+        match self.peek.kind {
+            TokenKind::LBrace | TokenKind::LBracket if self.depth >= DEFAULT_MAX_DEPTH => {
+                self.skip_too_deep()
+            }
+            TokenKind::LBrace => {
+                self.depth += 1;
+                let value = self.object(bump);
+                self.depth -= 1;
+
+                value
+            }
+            TokenKind::LBracket => {
+                self.depth += 1;
+                let value = self.array(bump);
+                self.depth -= 1;
+
+                value
+            }
+            TokenKind::String => JsonValue::String(unescape_in(self.advance().origin, bump)),
+            TokenKind::Number => self.number(),
+            TokenKind::True => {
+                self.advance();
+                JsonValue::Boolean(true)
+            }
+            TokenKind::False => {
+                self.advance();
+                JsonValue::Boolean(false)
+            }
+            TokenKind::Null => {
+                self.advance();
+                JsonValue::Null
+            }
+            _ => {
+                self.error(&VALUE_START, Some(ErrorContext::Value));
+                self.skip_unless_resumable();
+
+                JsonValue::Null
+            }
+        }
+    }
+
+    /// Reports the container at the next token as nested too deeply and consumes it up to
+    /// its closing bracket without recursing, so that it becomes `null`.
+    fn skip_too_deep(&mut self) -> JsonValue<'a> {
+        self.error(&[], Some(ErrorContext::TooDeeplyNested(DEFAULT_MAX_DEPTH)));
+
+        let mut open = 0_usize;
+
+        while self.peek.kind != TokenKind::Eof {
+            match self.advance().kind {
+                TokenKind::LBrace | TokenKind::LBracket => open += 1,
+                TokenKind::RBrace | TokenKind::RBracket => {
+                    open -= 1;
+
+                    if open == 0 {
+                        break;
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        JsonValue::Null
+    }
+
+    fn number(&mut self) -> JsonValue<'a> {
+        let literal = self.peek.origin;
+
+        let Ok(n) = literal.parse::<f64>() else {
+            self.errors.push(
+                ExpectedTokenError::new([TokenKind::Number], illegal_number!(ParseFloatError))
+                    .with_origin(literal)
+                    .at(self.lexer.row, self.peek.start_column)
+                    .with_span(self.peek.span),
+            );
+            self.recovered.insert(self.path.path().to_owned());
+            self.advance();
+
+            return JsonValue::Null;
+        };
+
+        if loses_precision(literal, n) {
+            self.warnings.push(Warning::PrecisionLoss {
+                pointer: self.path.path().to_owned(),
+                text: literal.to_owned(),
+            });
+        }
+
+        self.advance();
+
+        JsonValue::Number(n)
+    }
+
+    fn array(&mut self, bump: &'a Bump) -> JsonValue<'a> {
+        self.advance();
+
+        let mut items = Vec::new_in(bump);
+
+        if self.peek.kind == TokenKind::RBracket {
+            self.advance();
+
+            return JsonValue::Array(items);
+        }
+
+        if self.peek.kind == TokenKind::Comma {
+            let mut expected = VALUE_START.to_vec();
+
+            expected.push(TokenKind::RBracket);
+            self.error(&expected, Some(ErrorContext::LeadingComma));
+            self.advance();
+        }
+
+        loop {
+            let len = self.path.push_index(items.len());
+
+            items.push(self.value(bump));
+            self.path.pop(len);
+
+            match self.peek.kind {
+                TokenKind::Comma => {
+                    self.advance();
+                }
+                TokenKind::RBracket => {
+                    self.advance();
+                    break;
+                }
+                TokenKind::RBrace | TokenKind::Eof => {
+                    self.error(&[TokenKind::Comma, TokenKind::RBracket], None);
+                    break;
+                }
+                kind => {
+                    let context = kind
+                        .starts_value()
+                        .then_some(ErrorContext::MissingArrayComma);
+
+                    self.error(&[TokenKind::Comma, TokenKind::RBracket], context);
+                }
+            }
+        }
+
+        JsonValue::Array(items)
+    }
+
+    fn object(&mut self, bump: &'a Bump) -> JsonValue<'a> {
+        self.advance();
+
+        let mut properties = Vec::new_in(bump);
+
+        if self.peek.kind == TokenKind::RBrace {
+            self.advance();
+
+            return JsonValue::Object(properties);
+        }
+
+        if self.peek.kind == TokenKind::Comma {
+            self.error(
+                &[TokenKind::String, TokenKind::RBrace],
+                Some(ErrorContext::LeadingComma),
+            );
+            self.advance();
+        }
+
+        loop {
+            if self.peek.kind == TokenKind::String {
+                let key = unescape_in(self.advance().origin, bump);
+
+                if self.peek.kind == TokenKind::Colon {
+                    self.advance();
+                } else {
+                    self.error(&[TokenKind::Colon], Some(ErrorContext::ObjectColon));
+                }
+
+                let len = self.path.push_key(key);
+                let value = self.value(bump);
+
+                self.path.pop(len);
+                properties.push(JsonProperty {
+                    key: borrowed_key(key),
+                    value,
+                    leading_comment: None,
+                });
+            } else {
+                self.error(&[TokenKind::String], Some(ErrorContext::ObjectKey));
+                self.skip_unless_resumable();
+
+                // Skip the value of a member with an invalid key, such as `{1: 2}`, so it
+                // is reported once
+                if self.peek.kind == TokenKind::Colon {
+                    self.advance();
+                    self.value(bump);
+                }
+            }
+
+            match self.peek.kind {
+                TokenKind::Comma => {
+                    self.advance();
+                }
+                TokenKind::RBrace => {
+                    self.advance();
+                    break;
+                }
+                TokenKind::RBracket | TokenKind::Eof => {
+                    self.error(&[TokenKind::Comma, TokenKind::RBrace], None);
+                    break;
+                }
+                kind => {
+                    let context =
+                        (kind == TokenKind::String).then_some(ErrorContext::MissingObjectComma);
+
+                    self.error(&[TokenKind::Comma, TokenKind::RBrace], context);
+                }
+            }
+        }
+
+        JsonValue::Object(properties)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn recover_after_errors() {
+        let json = r#"{"a": [1, @, 3], "b" 2, "c": {1: true}, "d": "x"} tail"#;
+
+        let bump = Bump::new();
+        let recovered = parse_recovering(json, &bump);
+
+        assert_eq!(
+            recovered.value,
+            Parser::new(r#"{"a": [1, null, 3], "b": 2, "c": {}, "d": "x"}"#)
+                .parse(&bump)
+                .unwrap()
+        );
+        assert_eq!(
+            recovered
+                .errors
+                .iter()
+                .map(|err| (err.span.start, err.context))
+                .collect::<std::vec::Vec<_>>(),
+            [
+                (10, Some(ErrorContext::Value)),
+                (21, Some(ErrorContext::ObjectColon)),
+                (30, Some(ErrorContext::ObjectKey)),
+                (50, None),
+            ]
+        );
+        assert_eq!(
+            recovered.errors[0],
+            Parser::new(json).parse(&bump).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn recovered_values_identified() {
+        let json = r#"{"user": {"name": "Ann", "age": }, "tags": ["a", "b"]}"#;
+
+        let bump = Bump::new();
+        let recovered = parse_recovering(json, &bump);
+
+        assert_eq!(recovered.errors.len(), 1);
+        assert_eq!(
+            recovered.recovered,
+            BTreeSet::from(["/user/age".to_owned()])
+        );
+        assert_eq!(recovered.value.pointer("/user/age"), Some(&JsonValue::Null));
+        assert!(recovered.is_recovered("/user/age"));
+        assert!(!recovered.is_recovered("/user"));
+        assert!(!recovered.is_recovered("/user/name"));
+        assert!(!recovered.is_recovered("/user/ag"));
+
+        // Skipping a member leaves the object itself incomplete, along with all it contains
+        let skipped = parse_recovering(r#"{"a": {"b": [1], 2: 3}, "c": 4} x"#, &bump);
+
+        assert_eq!(skipped.recovered, BTreeSet::from(["/a".to_owned()]));
+        assert!(skipped.is_recovered("/a/b/0"));
+        assert!(!skipped.is_recovered("/c"));
+        assert_eq!(skipped.errors.len(), 2);
+
+        assert!(parse_recovering("[1, 2]", &bump).recovered.is_empty());
+    }
+
+    #[test]
+    fn every_trailing_token() {
+        let bump = Bump::new();
+        let recovered = parse_recovering("{} extra 1 ]", &bump);
+
+        assert_eq!(recovered.value, JsonValue::Object(Vec::new_in(&bump)));
+        assert_eq!(
+            recovered
+                .errors
+                .iter()
+                .map(|err| (err.origin.as_str(), err.expected.as_slice()))
+                .collect::<std::vec::Vec<_>>(),
+            [
+                ("extra", [TokenKind::Eof].as_slice()),
+                ("1", &[TokenKind::Eof]),
+                ("]", &[TokenKind::Eof]),
+            ]
+        );
+    }
+
+    #[test]
+    fn recover_valid_input() {
+        let json = include_str!("../../test_data/log.json");
+
+        let bump = Bump::new();
+        let recovered = parse_recovering(json, &bump);
+
+        assert_eq!(recovered.value, Parser::new(json).parse(&bump).unwrap());
+        assert!(recovered.errors.is_empty());
+    }
+
+    #[test]
+    fn skips_containers_nested_too_deeply() {
+        let bump = Bump::new();
+        let nested = format!("[{}{}, 1]", "[".repeat(200), "]".repeat(200));
+        let recovered = parse_recovering(&nested, &bump);
+
+        // The root is at depth 1, so the first array past the limit is reached through
+        // `DEFAULT_MAX_DEPTH` zeros
+        let too_deep = "/0".repeat(DEFAULT_MAX_DEPTH);
+
+        assert_eq!(recovered.errors.len(), 1);
+        assert_eq!(
+            recovered.errors[0],
+            Parser::new(&nested).parse(&bump).unwrap_err()
+        );
+        assert_eq!(recovered.recovered, BTreeSet::from([too_deep.clone()]));
+        assert_eq!(recovered.value.pointer(&too_deep), Some(&JsonValue::Null));
+        assert_eq!(recovered.value.pointer("/1"), Some(&JsonValue::Number(1.0)));
+
+        // Unclosed brackets are skipped up to the end of the input rather than recursed into
+        let adversarial = "[".repeat(100_000);
+        let recovered = parse_recovering(&adversarial, &bump);
+
+        assert_eq!(
+            recovered.errors[0].context,
+            Some(ErrorContext::TooDeeplyNested(DEFAULT_MAX_DEPTH))
+        );
+        assert_eq!(recovered.errors[0].span.start, DEFAULT_MAX_DEPTH);
+        assert!(
+            recovered.errors[1..]
+                .iter()
+                .all(|err| err.actual == TokenKind::Eof)
+        );
+    }
+
+    #[test]
+    fn precision_loss_warning() {
+        let bump = Bump::new();
+        let recovered = parse_recovering(
+            r#"{"ids": [123456789012345, 12345678901234567, 1.2345678901234567, 9007199254740993]}"#,
+            &bump,
+        );
+
+        assert!(recovered.errors.is_empty());
+        assert_eq!(
+            recovered.warnings,
+            [
+                Warning::PrecisionLoss {
+                    pointer: "/ids/1".to_owned(),
+                    text: "12345678901234567".to_owned(),
+                },
+                Warning::PrecisionLoss {
+                    pointer: "/ids/3".to_owned(),
+                    text: "9007199254740993".to_owned(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::token::Span;
+
+/// Byte spans of every value produced by [`Parser::parse_with_sources`], keyed by JSON Pointer.
+///
+/// [`Parser::parse_with_sources`]: crate::parser::Parser::parse_with_sources
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap<'a> {
+    input: &'a str,
+    spans: BTreeMap<String, Span>,
+}
+
+impl<'a> SourceMap<'a> {
+    /// Returns the byte span of the value at `pointer`.
+    pub fn span(&self, pointer: &str) -> Option<Span> {
+        self.spans.get(pointer).copied()
+    }
+
+    /// Returns the exact source text of the value at `pointer`, including any inner
+    /// whitespace and the surrounding quotes or brackets.
+    pub fn source(&self, pointer: &str) -> Option<&'a str> {
+        self.span(pointer)
+            .map(|span| &self.input[span.start..span.end])
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SourceRecorder {
+    path: String,
+    spans: BTreeMap<String, Span>,
+    path_only: bool, // track the current path without recording any spans
+}
+
+impl SourceRecorder {
+    /// A recorder that only tracks the current path, for parsing that depends on it.
+    pub(crate) fn path_only() -> Self {
+        Self {
+            path_only: true,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Appends an object key to the current path, returning the length to restore afterwards.
+    pub(crate) fn push_key(&mut self, key: &str) -> usize {
+        let len = self.path.len();
+
+        self.path.push('/');
+        self.path
+            .push_str(&key.replace('~', "~0").replace('/', "~1"));
+
+        len
+    }
+
+    /// Appends an array index to the current path, returning the length to restore afterwards.
+    pub(crate) fn push_index(&mut self, index: usize) -> usize {
+        let len = self.path.len();
+
+        let _ = write!(self.path, "/{index}");
+
+        len
+    }
+
+    pub(crate) fn pop(&mut self, len: usize) {
+        self.path.truncate(len);
+    }
+
+    pub(crate) fn record(&mut self, span: Span) {
+        if !self.path_only {
+            self.spans.insert(self.path.clone(), span);
+        }
+    }
+
+    pub(crate) fn finish(self, input: &str) -> SourceMap<'_> {
+        SourceMap {
+            input,
+            spans: self.spans,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn source_of_nested_array() {
+        let json = r#"{
+    "a": [1, [2,  3 ], "four"],
+    "b/c": {}
+}"#;
+
+        let bump = Bump::new();
+        let (value, sources) = Parser::new(json).parse_with_sources(&bump).unwrap();
+
+        assert_eq!(value, Parser::new(json).parse(&bump).unwrap());
+        assert_eq!(sources.source(""), Some(json));
+        assert_eq!(sources.source("/a"), Some(r#"[1, [2,  3 ], "four"]"#));
+        assert_eq!(sources.source("/a/1"), Some("[2,  3 ]"));
+        assert_eq!(sources.source("/a/1/1"), Some("3"));
+        assert_eq!(sources.source("/a/2"), Some(r#""four""#));
+        assert_eq!(sources.source("/b~1c"), Some("{}"));
+        assert_eq!(sources.span("/a/1"), Some(Span { start: 15, end: 23 }));
+        assert_eq!(sources.source("/missing"), None);
+    }
+}
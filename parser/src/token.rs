@@ -1,12 +1,20 @@
 use std::str::Chars;
 
-use crate::{error::IllegalReason, illegal_number, illegal_string};
+use crate::{error::IllegalReason, illegal_number, illegal_string, options::ParserOptions};
+
+/// Byte range of a token within the lexer input.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
 pub struct Token<'a> {
     pub kind: TokenKind,
     pub origin: &'a str,
     pub start_column: usize,
+    pub span: Span,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -28,6 +36,35 @@ pub enum TokenKind {
     Eof,
 }
 
+impl TokenKind {
+    /// Returns `true` for the structural tokens `{`, `}`, `[`, `]`, `:` and `,`.
+    pub fn is_delimiter(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::LBrace
+                | TokenKind::RBrace
+                | TokenKind::LBracket
+                | TokenKind::RBracket
+                | TokenKind::Colon
+                | TokenKind::Comma
+        )
+    }
+
+    /// Returns `true` for the tokens that can begin a JSON value.
+    pub fn starts_value(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::String
+                | TokenKind::Number
+                | TokenKind::True
+                | TokenKind::False
+                | TokenKind::Null
+                | TokenKind::LBrace
+                | TokenKind::LBracket
+        )
+    }
+}
+
 impl Default for TokenKind {
     fn default() -> Self {
         Self::Illegal(None)
@@ -68,10 +105,20 @@ pub struct Lexer<'a> {
     pub column: usize,
     ch: Option<char>,
     chars: Chars<'a>,
+    allow_comments: bool,
+    max_token_len: Option<usize>,
+    relaxed_escapes: bool,
+    allow_leading_zeros: bool,
+    allow_non_finite: bool,
+    comment: Option<Span>, // comments skipped before the most recent token
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::new_with_options(input, &ParserOptions::default())
+    }
+
+    pub fn new_with_options(input: &'a str, options: &ParserOptions) -> Self {
         let chars = input.chars();
 
         let mut lexer = Self {
@@ -82,10 +129,39 @@ impl<'a> Lexer<'a> {
             column: 0,
             ch: None,
             chars,
+            allow_comments: options.allow_comments,
+            max_token_len: options.max_token_len,
+            relaxed_escapes: options.relaxed_escapes,
+            allow_leading_zeros: options.allow_leading_zeros,
+            allow_non_finite: options.allow_non_finite,
+            comment: None,
         };
 
         lexer.read_char();
 
+        // A leading byte order mark is ignored and doesn't count as a column
+        if lexer.ch == Some('\u{FEFF}') {
+            lexer.read_char();
+            lexer.column -= 1;
+        }
+
+        lexer
+    }
+
+    /// Starts lexing at the byte offset `start` of `input`, with rows, columns and spans
+    /// still reported relative to the whole of `input`.
+    ///
+    /// Panics if `start` is not on a `char` boundary.
+    pub fn new_at(input: &'a str, start: usize, options: &ParserOptions) -> Self {
+        assert!(
+            input.is_char_boundary(start),
+            "start offset {start} is not a char boundary"
+        );
+
+        let mut lexer = Self::new_with_options(input, options);
+
+        lexer.skip_to(start);
+
         lexer
     }
 
@@ -108,6 +184,8 @@ impl<'a> Lexer<'a> {
     }
 
     fn skip_whitespace(&mut self) {
+        self.comment = None;
+
         loop {
             match self.ch {
                 Some(' ' | '\t' | '\r') => (),
@@ -115,6 +193,13 @@ impl<'a> Lexer<'a> {
                     self.row += 1;
                     self.column = 0;
                 }
+                Some('/') if self.allow_comments => {
+                    if self.skip_comment() {
+                        continue;
+                    }
+
+                    break;
+                }
                 _ => break,
             }
 
@@ -122,10 +207,58 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Skips the comment starting at the current `/`. Returns `false` and leaves the lexer
+    /// untouched if there is no comment here or the block comment is never closed.
+    fn skip_comment(&mut self) -> bool {
+        let start = self.position;
+
+        match self.input.as_bytes().get(self.read_position) {
+            Some(b'/') => {
+                while !matches!(self.ch, Some('\n') | None) {
+                    self.read_char();
+                }
+            }
+            Some(b'*') => {
+                let Some(len) = self.input[start + 2..].find("*/") else {
+                    return false;
+                };
+
+                self.skip_to(start + 2 + len + 2);
+            }
+            _ => return false,
+        }
+
+        let start = self.comment.map_or(start, |comment| comment.start);
+
+        self.comment = Some(self.span_from(start));
+
+        true
+    }
+
+    /// Advances to the byte offset `end`, keeping `row` and `column` up to date.
+    fn skip_to(&mut self, end: usize) {
+        while self.position < end {
+            if self.ch == Some('\n') {
+                self.row += 1;
+                self.column = 0;
+            }
+
+            self.read_char();
+        }
+    }
+
+    /// Returns the comments skipped before the most recent token, from the start of the
+    /// first to the end of the last, including their delimiters.
+    pub fn take_comment(&mut self) -> Option<&'a str> {
+        self.comment
+            .take()
+            .map(|comment| &self.input[comment.start..comment.end])
+    }
+
     fn read_ident(&mut self) -> &'a str {
         let start_pos = self.position;
 
-        while matches!(self.ch, Some(c) if c.is_ascii_lowercase()) {
+        while matches!(self.ch, Some(c) if c.is_ascii_alphanumeric() || c == '_') {
             self.read_char();
         }
 
@@ -156,8 +289,39 @@ impl<'a> Lexer<'a> {
 
         let codepoint = &self.input[start_pos..self.position];
 
-        if !u32::from_str_radix(codepoint, 16).is_ok_and(|v| v <= 0x10FFFF) {
-            return illegal_string!(InvalidUnicode, start_column - 2);
+        match u32::from_str_radix(codepoint, 16) {
+            // A high surrogate must be followed by a low one, which are consumed together as
+            // a single character
+            Ok(0xD800..=0xDBFF) if self.at_low_surrogate() => {
+                for _ in 0..6 {
+                    self.read_char();
+                }
+
+                None
+            }
+            Ok(0xD800..=0xDFFF) | Err(_) => illegal_string!(InvalidUnicode, start_column - 2),
+            Ok(_) => None,
+        }
+    }
+
+    /// Returns `true` if the input continues with a `\uDC00`..`\uDFFF` escape.
+    fn at_low_surrogate(&self) -> bool {
+        self.input[self.position..]
+            .strip_prefix("\\u")
+            .and_then(|rest| rest.get(..4))
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .is_some_and(|low| (0xDC00..=0xDFFF).contains(&low))
+    }
+
+    fn is_legal_hex_escape(&mut self) -> Option<IllegalReason> {
+        let start_column = self.column;
+
+        for _ in 0..2 {
+            if !matches!(self.ch, Some(c) if c.is_ascii_hexdigit()) {
+                return illegal_string!(InvalidEscape, start_column - 2);
+            }
+
+            self.read_char();
         }
 
         None
@@ -194,6 +358,11 @@ impl<'a> Lexer<'a> {
                         if matches!(self.ch, Some('u')) {
                             self.read_char();
                             illegal_reason = self.is_legal_unicode();
+                        } else if self.relaxed_escapes && matches!(self.ch, Some('0')) {
+                            self.read_char();
+                        } else if self.relaxed_escapes && matches!(self.ch, Some('x')) {
+                            self.read_char();
+                            illegal_reason = self.is_legal_hex_escape();
                         } else {
                             illegal_reason = illegal_string!(InvalidEscape, self.column - 1);
                         }
@@ -207,6 +376,9 @@ impl<'a> Lexer<'a> {
                 '\n' if illegal_reason.is_none() => {
                     illegal_reason = illegal_string!(UnescapedNewLine, self.column);
                 }
+                '\0'..='\x1f' if illegal_reason.is_none() => {
+                    illegal_reason = illegal_string!(UnescapedControlCharacter, self.column);
+                }
                 _ => (),
             };
 
@@ -223,9 +395,49 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    fn too_long(&self, literal: &str) -> Option<IllegalReason> {
+        self.max_token_len
+            .filter(|max| literal.len() > *max)
+            .map(IllegalReason::TooLong)
+    }
+
+    /// Returns how many bytes of the input have been consumed so far.
+    pub fn byte_offset(&self) -> usize {
+        self.position.min(self.input.len())
+    }
+
+    pub(crate) fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// Returns the row and column of the byte offset `offset`, counted the same way as for
+    /// tokens.
+    pub(crate) fn position_of(&self, offset: usize) -> (usize, usize) {
+        let before = &self.input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        let line = &before[line_start..];
+
+        // A leading byte order mark doesn't count as a column
+        let line = if line_start == 0 {
+            line.strip_prefix('\u{FEFF}').unwrap_or(line)
+        } else {
+            line
+        };
+
+        (before.matches('\n').count() + 1, line.chars().count() + 1)
+    }
+
+    fn span_from(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.position,
+        }
+    }
+
     pub fn next_token(&mut self) -> Token<'a> {
         self.skip_whitespace();
 
+        let start = self.position;
         let start_column = self.column;
 
         let kind = match self.ch {
@@ -237,7 +449,7 @@ impl<'a> Lexer<'a> {
             Some(',') => TokenKind::Comma,
             Some('"') => {
                 let (str, illegal_reason) = self.read_string();
-                let kind = match illegal_reason {
+                let kind = match illegal_reason.or_else(|| self.too_long(str)) {
                     Some(reason) => TokenKind::Illegal(Some(reason)),
                     None => TokenKind::String,
                 };
@@ -246,30 +458,62 @@ impl<'a> Lexer<'a> {
                     kind,
                     origin: str,
                     start_column,
+                    span: self.span_from(start),
                 };
             }
-            Some('t' | 'f' | 'n') => {
+            // The whole identifier is read so that a mistake such as `undefined` or `True` is
+            // reported in full
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
                 let ident = self.read_ident();
 
                 let kind = match ident {
                     "true" => TokenKind::True,
                     "false" => TokenKind::False,
                     "null" => TokenKind::Null,
-                    _ => TokenKind::Illegal(None),
+                    "NaN" | "Infinity" if self.allow_non_finite => TokenKind::Number,
+                    _ => TokenKind::Illegal(Some(IllegalReason::UnknownLiteral)),
                 };
 
                 return Token {
                     kind,
                     origin: ident,
                     start_column,
+                    span: self.span_from(start),
+                };
+            }
+            Some('-')
+                if self.allow_non_finite
+                    && self.input[self.read_position..].starts_with("Infinity") =>
+            {
+                self.read_char();
+
+                let kind = match self.read_ident() {
+                    "Infinity" => TokenKind::Number,
+                    _ => TokenKind::Illegal(Some(IllegalReason::UnknownLiteral)),
+                };
+
+                return Token {
+                    kind,
+                    origin: &self.input[start..self.position],
+                    start_column,
+                    span: self.span_from(start),
                 };
             }
             Some('-' | '0'..='9') => {
                 let num = self.read_number();
 
                 let kind = match num.as_bytes() {
-                    [b'0', b'0'..=b'9', ..] => illegal_number!(LeadingZero),
-                    [b'0', b'e' | b'E', ..] => illegal_number!(MissingExponent),
+                    // Checked first so overlong literals never reach the classification or
+                    // `f64` parsing
+                    _ if let Some(reason) = self.too_long(num) => TokenKind::Illegal(Some(reason)),
+                    [b'0', b'0'..=b'9', ..] | [b'-', b'0', b'0'..=b'9', ..]
+                        if !self.allow_leading_zeros =>
+                    {
+                        illegal_number!(LeadingZero)
+                    }
+                    [.., b'e' | b'E'] | [.., b'e' | b'E', b'+' | b'-'] => {
+                        illegal_number!(MissingExponent)
+                    }
                     [b'-', b'.', ..] => illegal_number!(InvalidFractionPart),
                     [.., b'.'] => illegal_number!(MissingFraction),
                     [.., b'-'] => illegal_number!(MinusMissingDigit),
@@ -284,6 +528,19 @@ impl<'a> Lexer<'a> {
                     kind,
                     origin: num,
                     start_column,
+                    span: self.span_from(start),
+                };
+            }
+            Some('/')
+                if self.allow_comments && self.input[self.read_position..].starts_with('*') =>
+            {
+                self.skip_to(self.input.len());
+
+                return Token {
+                    kind: TokenKind::Illegal(Some(IllegalReason::UnterminatedComment)),
+                    origin: &self.input[start..],
+                    start_column,
+                    span: self.span_from(start),
                 };
             }
             _ if self.position >= self.input.len() => {
@@ -291,8 +548,12 @@ impl<'a> Lexer<'a> {
 
                 return Token {
                     kind: TokenKind::Eof,
+                    origin: &self.input[self.input.len()..],
                     start_column: start_column + 1,
-                    ..Default::default()
+                    span: Span {
+                        start: self.input.len(),
+                        end: self.input.len(),
+                    },
                 };
             }
             _ => TokenKind::Illegal(None),
@@ -306,6 +567,7 @@ impl<'a> Lexer<'a> {
             kind,
             origin,
             start_column,
+            span: self.span_from(start),
         }
     }
 }
@@ -322,10 +584,38 @@ impl<'a> Iterator for Lexer<'a> {
     }
 }
 
+/// Runs the lexer over the whole input and collects every illegal token together with the
+/// reason it was rejected, rather than stopping at the first one like the parser does.
+///
+/// Tokens rejected without a specific reason, such as stray characters, are reported as
+/// [`IllegalReason::Character`] with their first character, and misspelled literals as
+/// [`IllegalReason::UnknownLiteral`].
+pub fn lint(input: &str) -> Vec<(Token<'_>, IllegalReason)> {
+    Lexer::new(input)
+        .filter_map(|token| match token.kind {
+            TokenKind::Illegal(Some(reason)) => Some((token, reason)),
+            TokenKind::Illegal(None) => token
+                .origin
+                .chars()
+                .next()
+                .map(|ch| (token, IllegalReason::Character(ch))),
+            _ => None,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn lint_collects_every_illegal_token() {
+        let json = r#"{"a": 01, "b": "tab\x", "c": 1.e5, "d": @, "e": nul}"#;
+
+        insta::assert_debug_snapshot!(lint(json));
+        assert!(lint(r#"{"valid": [1, 2.5e3, "three"]}"#).is_empty());
+    }
+
     #[test]
     fn tokenize_simple() {
         let json = r#"
@@ -350,6 +640,47 @@ mod tests {
         insta::assert_debug_snapshot!(&lexer.collect::<Vec<_>>());
     }
 
+    #[test]
+    fn tokenize_comments() {
+        let jsonc = ParserOptions {
+            allow_comments: true,
+            ..Default::default()
+        };
+        let json = "// header\n{/* spans\n   lines */ \"a\": 1}";
+
+        let mut lexer = Lexer::new_with_options(json, &jsonc);
+        let kinds = |lexer: Lexer| lexer.map(|token| token.kind).collect::<Vec<_>>();
+
+        let brace = lexer.next_token();
+
+        assert_eq!((brace.kind, brace.start_column), (TokenKind::LBrace, 1));
+        assert_eq!(lexer.row, 2);
+        assert_eq!(lexer.take_comment(), Some("// header"));
+
+        let key = lexer.next_token();
+
+        assert_eq!(key.kind, TokenKind::String);
+        assert_eq!(key.start_column, 13);
+        assert_eq!(lexer.row, 3);
+        assert_eq!(lexer.take_comment(), Some("/* spans\n   lines */"));
+
+        // Without the option comments stay illegal
+        assert!(matches!(kinds(Lexer::new(json))[0], TokenKind::Illegal(_)));
+
+        let unterminated = Lexer::new_with_options("[1, /* never closed\n2]", &jsonc);
+
+        assert_eq!(
+            kinds(unterminated),
+            [
+                TokenKind::LBracket,
+                TokenKind::Number,
+                TokenKind::Comma,
+                TokenKind::Illegal(Some(IllegalReason::UnterminatedComment)),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
     #[test]
     fn tokenize_escaped_1() {
         let json = r#"{"key":"Hello, \"world!\""}"#;
@@ -396,7 +727,7 @@ mod tests {
     }
 
     #[test]
-    fn tokenize_valid_unicode_3() {
+    fn tokenize_lone_high_surrogate_1() {
         let json = r#"{"key": "\udbcd"}"#;
 
         let lexer = Lexer::new(json);
@@ -405,7 +736,7 @@ mod tests {
     }
 
     #[test]
-    fn tokenize_valid_unicode_4() {
+    fn tokenize_lone_high_surrogate_2() {
         let json = r#"{"key": "\uda00"}"#;
 
         let lexer = Lexer::new(json);
@@ -458,6 +789,25 @@ mod tests {
         insta::assert_debug_snapshot!(&lexer.collect::<Vec<_>>());
     }
 
+    #[test]
+    fn surrogate_pairs() {
+        let kind = |json| Lexer::new(json).next_token().kind;
+        let invalid_unicode = |column| TokenKind::Illegal(illegal_string!(InvalidUnicode, column));
+
+        assert_eq!(kind(r#""\ud83d\ude00""#), TokenKind::String);
+        assert_eq!(kind(r#""G clef \uD834\uDD1E!""#), TokenKind::String);
+
+        // A lone high surrogate, then one followed by something other than a low surrogate
+        assert_eq!(kind(r#""\ud83d""#), invalid_unicode(2));
+        assert_eq!(kind(r#""a \ud83d b""#), invalid_unicode(4));
+        assert_eq!(kind(r#""\ud83d\u0041""#), invalid_unicode(2));
+        assert_eq!(kind(r#""\ud83d\ud83d""#), invalid_unicode(2));
+        assert_eq!(kind(r#""\ud83d\n""#), invalid_unicode(2));
+
+        // A low surrogate without a high one before it
+        assert_eq!(kind(r#""x\ude00""#), invalid_unicode(3));
+    }
+
     #[test]
     fn tokenize_invalid_escape_1() {
         let json = r#"{"key": "\"}"#;
@@ -484,4 +834,28 @@ mod tests {
 
         insta::assert_debug_snapshot!(&lexer.collect::<Vec<_>>());
     }
+
+    #[test]
+    fn unknown_literals() {
+        for word in ["undefined", "None", "TRUE", "nul", "true_1"] {
+            let json = format!("[{word}]");
+            let token = Lexer::new(&json).nth(1).unwrap();
+
+            assert_eq!(
+                token.kind,
+                TokenKind::Illegal(Some(IllegalReason::UnknownLiteral))
+            );
+            assert_eq!(token.origin, word);
+        }
+
+        let bump = bumpalo::Bump::new();
+        let err = crate::parser::Parser::new(r#"{"a": undefined}"#)
+            .parse(&bump)
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "unknown literal 'undefined' at row 1 column 7; expected true, false, or null"
+        );
+    }
 }
@@ -0,0 +1,532 @@
+use std::{
+    fmt::Display,
+    io::{self, Read},
+};
+
+use crate::options::DEFAULT_MAX_DEPTH;
+
+#[derive(Debug)]
+pub enum ValidationError {
+    Io(io::Error),
+    /// The input is not valid JSON. Points at the first character that can't be accepted,
+    /// or one past the end of the input if it ends early.
+    Invalid {
+        row: usize,
+        col: usize,
+    },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::Io(e) => write!(f, "failed to read input: {e}"),
+            ValidationError::Invalid { row, col } => {
+                write!(f, "invalid JSON at row {row} column {col}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<io::Error> for ValidationError {
+    fn from(e: io::Error) -> Self {
+        ValidationError::Io(e)
+    }
+}
+
+/// Checks that `reader` contains a single valid JSON document without buffering it.
+///
+/// Accepts exactly what [`Parser::parse`] accepts with the default options, including their
+/// limit of [`DEFAULT_MAX_DEPTH`] levels of nesting. Memory use is bounded by that depth
+/// rather than the size of the document.
+///
+/// [`Parser::parse`]: crate::parser::Parser::parse
+pub fn validate_reader<R: Read>(mut reader: R) -> Result<(), ValidationError> {
+    let mut validator = Validator::default();
+    let mut buf = [0; 8 * 1024];
+
+    loop {
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        validator.feed(&buf[..read])?;
+    }
+
+    validator.finish()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberState {
+    Minus,
+    Zero,
+    Integer,
+    Dot,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+impl NumberState {
+    fn is_complete(self) -> bool {
+        matches!(
+            self,
+            NumberState::Zero
+                | NumberState::Integer
+                | NumberState::Fraction
+                | NumberState::ExponentDigits
+        )
+    }
+
+    fn next(self, byte: u8) -> Option<Self> {
+        use NumberState::*;
+
+        Some(match (self, byte) {
+            (Minus, b'0') => Zero,
+            (Minus, b'1'..=b'9') => Integer,
+            (Integer, b'0'..=b'9') => Integer,
+            (Zero | Integer, b'.') => Dot,
+            (Dot | Fraction, b'0'..=b'9') => Fraction,
+            (Zero | Integer | Fraction, b'e' | b'E') => Exponent,
+            (Exponent, b'+' | b'-') => ExponentSign,
+            (Exponent | ExponentSign | ExponentDigits, b'0'..=b'9') => ExponentDigits,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    Backslash,
+    /// Inside a `\u` escape, with the number of hex digits still expected and the value of
+    /// those read so far
+    Unicode {
+        remaining: u8,
+        code: u16,
+        /// The escape must be a low surrogate completing the pair started by the last one
+        low: bool,
+    },
+    /// After a high surrogate escape, expecting the `\` of its low surrogate
+    HighSurrogate,
+    /// After the `\` following a high surrogate escape, expecting `u`
+    HighSurrogateBackslash,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Expecting a value, at the top level or after `:` or an array `,`
+    Value,
+    /// After `[`, expecting a value or `]`
+    ArrayStart,
+    /// After `{`, expecting a key or `}`
+    ObjectStart,
+    /// After an object `,`, expecting a key
+    Key,
+    Colon,
+    /// After a complete value, expecting `,`, a closing bracket or the end of input
+    AfterValue,
+    String {
+        key: bool,
+        escape: Escape,
+    },
+    Number(NumberState),
+    Literal(&'static [u8]),
+}
+
+/// Push-based validator that is fed the input a chunk at a time.
+#[derive(Debug)]
+struct Validator {
+    stack: Vec<Container>,
+    state: State,
+    /// Bytes of a UTF-8 sequence split across chunks
+    partial: Vec<u8>,
+    row: usize,
+    col: usize,
+    /// Whether any input has been seen, after which a byte order mark is no longer skipped
+    started: bool,
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self {
+            stack: Vec::new(),
+            state: State::Value,
+            partial: Vec::new(),
+            row: 1,
+            col: 0,
+            started: false,
+        }
+    }
+}
+
+impl Validator {
+    fn feed(&mut self, mut chunk: &[u8]) -> Result<(), ValidationError> {
+        if !self.partial.is_empty() {
+            let needed = utf8_len(self.partial[0]) - self.partial.len();
+            let taken = needed.min(chunk.len());
+
+            self.partial.extend_from_slice(&chunk[..taken]);
+            chunk = &chunk[taken..];
+
+            if taken < needed {
+                return Ok(());
+            }
+
+            let sequence = std::mem::take(&mut self.partial);
+            self.check_utf8(&sequence)?;
+            self.feed_bytes(&sequence)?;
+        }
+
+        let complete = match std::str::from_utf8(chunk) {
+            Ok(_) => chunk.len(),
+            // Only a truncated sequence at the very end is carried over to the next chunk
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => {
+                self.feed_bytes(&chunk[..e.valid_up_to()])?;
+                return Err(self.invalid_next());
+            }
+        };
+
+        self.feed_bytes(&chunk[..complete])?;
+        self.partial.extend_from_slice(&chunk[complete..]);
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<(), ValidationError> {
+        if !self.partial.is_empty() {
+            return Err(self.invalid_next());
+        }
+
+        if let State::Number(number) = self.state {
+            if !number.is_complete() {
+                return Err(self.invalid_next());
+            }
+
+            self.state = State::AfterValue;
+        }
+
+        if self.state == State::AfterValue && self.stack.is_empty() {
+            Ok(())
+        } else {
+            Err(self.invalid_next())
+        }
+    }
+
+    fn check_utf8(&self, sequence: &[u8]) -> Result<(), ValidationError> {
+        match std::str::from_utf8(sequence) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(self.invalid_next()),
+        }
+    }
+
+    fn feed_bytes(&mut self, mut bytes: &[u8]) -> Result<(), ValidationError> {
+        // Only whole characters are fed, so a leading byte order mark is never split
+        if !self.started && !bytes.is_empty() {
+            self.started = true;
+            bytes = bytes.strip_prefix("\u{FEFF}".as_bytes()).unwrap_or(bytes);
+        }
+
+        for &byte in bytes {
+            // Continuation bytes belong to the character that started them
+            if byte & 0xC0 != 0x80 {
+                self.col += 1;
+            }
+
+            self.step(byte)?;
+
+            if byte == b'\n' {
+                self.row += 1;
+                self.col = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn step(&mut self, byte: u8) -> Result<(), ValidationError> {
+        let is_whitespace = matches!(byte, b' ' | b'\t' | b'\r' | b'\n');
+
+        self.state = match self.state {
+            State::String { key, escape } => match (escape, byte) {
+                (Escape::None, b'"') if key => State::Colon,
+                (Escape::None, b'"') => State::AfterValue,
+                (Escape::None, b'\\') => State::String {
+                    key,
+                    escape: Escape::Backslash,
+                },
+                (Escape::None, 0x00..=0x1F) => return Err(self.invalid()),
+                (Escape::None, _) => self.state,
+                (Escape::Backslash, b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => {
+                    State::String {
+                        key,
+                        escape: Escape::None,
+                    }
+                }
+                (Escape::Backslash, b'u') => State::String {
+                    key,
+                    escape: Escape::Unicode {
+                        remaining: 4,
+                        code: 0,
+                        low: false,
+                    },
+                },
+                (Escape::HighSurrogate, b'\\') => State::String {
+                    key,
+                    escape: Escape::HighSurrogateBackslash,
+                },
+                (Escape::HighSurrogateBackslash, b'u') => State::String {
+                    key,
+                    escape: Escape::Unicode {
+                        remaining: 4,
+                        code: 0,
+                        low: true,
+                    },
+                },
+                (
+                    Escape::Unicode {
+                        remaining,
+                        code,
+                        low,
+                    },
+                    digit,
+                ) if digit.is_ascii_hexdigit() => {
+                    // The digit was just checked, so it always converts
+                    let code = code << 4 | (char::from(digit).to_digit(16).unwrap_or(0) as u16);
+
+                    let escape = match (remaining, code, low) {
+                        (1, 0xDC00..=0xDFFF, true) => Escape::None,
+                        (1, 0xD800..=0xDBFF, false) => Escape::HighSurrogate,
+                        // Any other surrogate is unpaired
+                        (1, _, true) | (1, 0xDC00..=0xDFFF, false) => {
+                            return Err(self.invalid());
+                        }
+                        (1, _, false) => Escape::None,
+                        _ => Escape::Unicode {
+                            remaining: remaining - 1,
+                            code,
+                            low,
+                        },
+                    };
+
+                    State::String { key, escape }
+                }
+                _ => return Err(self.invalid()),
+            },
+            State::Number(number) => match number.next(byte) {
+                Some(next) => State::Number(next),
+                None if number.is_complete() => {
+                    self.state = State::AfterValue;
+
+                    return self.step(byte);
+                }
+                None => return Err(self.invalid()),
+            },
+            State::Literal([expected, rest @ ..]) if byte == *expected => match rest {
+                [] => State::AfterValue,
+                _ => State::Literal(rest),
+            },
+            State::Literal(_) => return Err(self.invalid()),
+            _ if is_whitespace => self.state,
+            State::Value | State::ArrayStart => match byte {
+                b']' if self.state == State::ArrayStart => self.close(Container::Array)?,
+                _ => self.start_value(byte)?,
+            },
+            State::ObjectStart | State::Key => match byte {
+                b'"' => State::String {
+                    key: true,
+                    escape: Escape::None,
+                },
+                b'}' if self.state == State::ObjectStart => self.close(Container::Object)?,
+                _ => return Err(self.invalid()),
+            },
+            State::Colon => match byte {
+                b':' => State::Value,
+                _ => return Err(self.invalid()),
+            },
+            State::AfterValue => match (self.stack.last(), byte) {
+                (Some(Container::Array), b',') => State::Value,
+                (Some(Container::Object), b',') => State::Key,
+                (Some(Container::Array), b']') => self.close(Container::Array)?,
+                (Some(Container::Object), b'}') => self.close(Container::Object)?,
+                _ => return Err(self.invalid()),
+            },
+        };
+
+        Ok(())
+    }
+
+    fn start_value(&mut self, byte: u8) -> Result<State, ValidationError> {
+        if matches!(byte, b'{' | b'[') && self.stack.len() >= DEFAULT_MAX_DEPTH {
+            return Err(self.invalid());
+        }
+
+        Ok(match byte {
+            b'{' => {
+                self.stack.push(Container::Object);
+                State::ObjectStart
+            }
+            b'[' => {
+                self.stack.push(Container::Array);
+                State::ArrayStart
+            }
+            b'"' => State::String {
+                key: false,
+                escape: Escape::None,
+            },
+            b'-' => State::Number(NumberState::Minus),
+            b'0' => State::Number(NumberState::Zero),
+            b'1'..=b'9' => State::Number(NumberState::Integer),
+            b't' => State::Literal(b"rue"),
+            b'f' => State::Literal(b"alse"),
+            b'n' => State::Literal(b"ull"),
+            _ => return Err(self.invalid()),
+        })
+    }
+
+    fn close(&mut self, container: Container) -> Result<State, ValidationError> {
+        match self.stack.pop() {
+            Some(open) if open == container => Ok(State::AfterValue),
+            _ => Err(self.invalid()),
+        }
+    }
+
+    /// Error at the character currently being stepped over.
+    fn invalid(&self) -> ValidationError {
+        ValidationError::Invalid {
+            row: self.row,
+            col: self.col,
+        }
+    }
+
+    /// Error at the character after the last one stepped over.
+    fn invalid_next(&self) -> ValidationError {
+        ValidationError::Invalid {
+            row: self.row,
+            col: self.col + 1,
+        }
+    }
+}
+
+fn utf8_len(first: u8) -> usize {
+    match first {
+        0xF0.. => 4,
+        0xE0.. => 3,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use bumpalo::Bump;
+
+    use super::*;
+    use crate::parser::Parser;
+
+    /// Hands out at most `chunk` bytes per read to exercise state carried across reads.
+    struct ChunkedReader<'a> {
+        input: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.chunk.min(buf.len()).min(self.input.len());
+
+            buf[..len].copy_from_slice(&self.input[..len]);
+            self.input = &self.input[len..];
+
+            Ok(len)
+        }
+    }
+
+    fn validate_chunked(input: &[u8], chunk: usize) -> Result<(), ValidationError> {
+        validate_reader(ChunkedReader { input, chunk })
+    }
+
+    #[test]
+    fn validate_chunked_reader() {
+        let json = r#"{"name": "Jo\"hn é é 😀", "scores": [95, -1.5e+3, 0], "ok": true, "none": null, "nested": [[], {}]}"#;
+
+        for chunk in [1, 2, 3, 7, 64] {
+            assert!(validate_chunked(json.as_bytes(), chunk).is_ok());
+        }
+
+        for (input, row, col) in [
+            (r#"{"a": 1,}"#, 1, 9),
+            ("[1, 2]\n  ]", 2, 3),
+            ("[01]", 1, 3),
+            ("[1.]", 1, 4),
+            (r#"["a\x"]"#, 1, 5),
+            ("[tru]", 1, 5),
+            ("[1, 2", 1, 6),
+            ("", 1, 1),
+        ] {
+            match validate_chunked(input.as_bytes(), 2) {
+                Err(ValidationError::Invalid { row: r, col: c }) => {
+                    assert_eq!((r, c), (row, col), "wrong position for {input:?}")
+                }
+                res => panic!("expected {input:?} to be invalid but got {res:?}"),
+            }
+        }
+
+        // A multi-byte character split across reads, followed by a truncated one
+        let mut bytes = "[\"é😀\"]".as_bytes().to_vec();
+        assert!(validate_chunked(&bytes, 1).is_ok());
+        bytes.insert(6, 0xF0);
+        assert!(validate_chunked(&bytes, 1).is_err());
+
+        // Only a leading byte order mark is skipped, even when split across reads
+        assert!(validate_chunked("\u{FEFF} {}".as_bytes(), 1).is_ok());
+        assert!(validate_chunked("\u{FEFF}".as_bytes(), 1).is_err());
+        assert!(validate_chunked("[\u{FEFF}]".as_bytes(), 1).is_err());
+    }
+
+    #[test]
+    fn validate_matches_parser() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_suite");
+
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let bytes = fs::read(&path).unwrap();
+
+            let parsed = std::str::from_utf8(&bytes)
+                .is_ok_and(|input| Parser::new(input).parse(&Bump::new()).is_ok());
+
+            assert_eq!(
+                validate_chunked(&bytes, 3).is_ok(),
+                parsed,
+                "validator disagrees with the parser on {path:?}"
+            );
+        }
+
+        // Surrogate pairs split at every point across chunks
+        for input in [
+            r#"["\ud83d\ude00"]"#,
+            r#"["\ud83d"]"#,
+            r#"["\ud83d\u0041"]"#,
+            r#"["\ud83d\n"]"#,
+            r#"["\ude00"]"#,
+        ] {
+            let parsed = Parser::new(input).parse(&Bump::new()).is_ok();
+
+            for chunk in 1..=4 {
+                assert_eq!(validate_chunked(input.as_bytes(), chunk).is_ok(), parsed);
+            }
+        }
+    }
+}
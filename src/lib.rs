@@ -20,7 +20,7 @@ pub unsafe extern "stdcall" fn get_key(input_str_ptr: *const u16, input_str_len:
     let parser = Parser::new(&input);
 
     let res = match parser.parse(&bump) {
-        Ok(res) => format!("{:?}", res.flattened()),
+        Ok(res) => res.flattened_json(),
         Err(e) => format!("{e}"),
     };
 